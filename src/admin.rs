@@ -0,0 +1,380 @@
+//! Admin API of the HTTP server.
+//!
+//! The API is enabled when the admin token is configured, every request must
+//! send it in the `Authorization: Bearer <token>` header. Requests and responses
+//! use JSON, errors are returned as `{"error": "..."}`.
+//!
+//! - `GET /admin/clients` - list connected clients
+//! - `GET /admin/clients/<id|nick>` - show the client
+//! - `POST /admin/clients/<id|nick>/kick` - kick the client, `{"reason": "..."}` (optional)
+//! - `POST /admin/clients/<id|nick>/send` - send `{"message": "..."}` to the client
+//! - `POST /admin/broadcast` - send `{"message": "..."}` to all clients
+//! - `POST /admin/bans` - ban `{"target": "...", "duration": "30m", "reason": "..."}`,
+//!   the target is the same as in the `/ban` command
+//! - `GET /admin/plugins` - list loaded plugins, commands and events
+//! - `POST /admin/plugins/rescan` - load new plugins from the plugins directory
+//!   (changed plugin files aren't reloaded, the server must be restarted)
+//!
+//! Actions of the API aren't made by a client, so plugin events (e.g. `onKick`)
+//! aren't executed for them.
+
+use async_std::task;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    audit::{self, Actor},
+    auth::{self, constant_time_eq},
+    history,
+    moderation::{self, Ban, BanTarget},
+    plugins::{prelude::*, PluginsManager},
+    rooms,
+    server::{self, HttpRequest, HttpResponse, CONFIG},
+    CLIENTS,
+};
+
+/// Name of the admin saved in bans created by the API
+const ADMIN_NAME: &str = "admin API";
+
+/// Error returned by the API.
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new<S>(status: u16, message: S) -> Self
+    where
+        S: ToString,
+    {
+        Self {
+            status,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::new(500, err)
+    }
+}
+
+type ApiResult = Result<HttpResponse, ApiError>;
+
+/// Connected client returned by the API.
+#[derive(Debug, Serialize)]
+struct ClientInfo {
+    id: usize,
+    name: String,
+    nick: Option<String>,
+    account: Option<String>,
+    transport: String,
+    /// Address of the client (`None` for Unix domain socket clients)
+    address: Option<String>,
+    roles: Vec<String>,
+    rooms: Vec<String>,
+    muted: bool,
+    connected_secs: u64,
+    idle_secs: u64,
+    messages_in: u64,
+    messages_out: u64,
+    bytes_in: u64,
+    bytes_out: u64,
+    commands: u64,
+    errors: u64,
+}
+
+impl From<&Client> for ClientInfo {
+    fn from(client: &Client) -> Self {
+        let metadata = &client.metadata;
+        let stats = metadata.stats();
+
+        let mut roles: Vec<String> = client.roles.lock().unwrap().iter().cloned().collect();
+        roles.sort();
+
+        Self {
+            id: client.id,
+            name: client.display_name(),
+            nick: client.nick.lock().unwrap().clone(),
+            account: client.account.lock().unwrap().clone(),
+            transport: metadata.transport.to_string(),
+            address: match metadata.peer_credentials {
+                Some(_) => None,
+                None => client.peer_addr().ok().map(|addr| addr.to_string()),
+            },
+            roles,
+            rooms: rooms::client_rooms(client.id),
+            muted: client.is_muted(),
+            connected_secs: metadata.connected_for().as_secs(),
+            idle_secs: metadata.idle_for().as_secs(),
+            messages_in: stats.messages_in,
+            messages_out: stats.messages_out,
+            bytes_in: stats.bytes_in,
+            bytes_out: stats.bytes_out,
+            commands: stats.commands,
+            errors: stats.errors,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct KickRequest {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct MessageRequest {
+    message: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct BanRequest {
+    /// IP address, network (CIDR), `account:<name>` or id or nickname of a
+    /// connected client
+    target: String,
+    /// Duration of the ban, e.g. `30m` or `2d` (permanent if `None`)
+    duration: Option<String>,
+    reason: Option<String>,
+}
+
+/// Handle the request to the admin API.
+pub fn handle(req: &HttpRequest) -> HttpResponse {
+    let token = CONFIG.read().unwrap().admin_token.clone();
+
+    // the API is disabled
+    let Some(token) = token else {
+        return HttpResponse::text(404, "not found");
+    };
+
+    let result = if is_authorized(req, &token) {
+        route(req)
+    } else {
+        info!("Rejected admin API request {} {}", req.method, req.path);
+
+        Err(ApiError::new(401, "invalid admin token"))
+    };
+
+    let response = result
+        .unwrap_or_else(|err| HttpResponse::json(err.status, &json!({ "error": err.message })));
+
+    audit::record(
+        &Actor::admin_api(req.peer_addr),
+        "admin_api",
+        Some(&format!("{} {}", req.method, req.path)),
+        Some(&response.status.to_string()),
+    );
+
+    response
+}
+
+/// Returns `true` if the request has the admin token.
+fn is_authorized(req: &HttpRequest, token: &str) -> bool {
+    let credentials = req
+        .headers
+        .get("authorization")
+        .and_then(|value| auth::parse_authorization(value));
+
+    match credentials {
+        Some(Credentials::Token(sent)) => constant_time_eq(sent.as_bytes(), token.as_bytes()),
+        _ => false,
+    }
+}
+
+fn route(req: &HttpRequest) -> ApiResult {
+    let path: Vec<&str> = req
+        .path
+        .trim_start_matches("/admin")
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    match (req.method.as_str(), path.as_slice()) {
+        ("GET", ["clients"]) => list_clients(),
+        ("GET", ["clients", target]) => {
+            let client = find_target(target)?;
+
+            Ok(HttpResponse::json(200, &ClientInfo::from(&client)))
+        },
+        ("POST", ["clients", target, "kick"]) => kick(req, target, body(req)?),
+        ("POST", ["clients", target, "send"]) => send(target, body(req)?),
+        ("POST", ["broadcast"]) => broadcast(body(req)?),
+        ("POST", ["bans"]) => ban(req, body(req)?),
+        ("GET", ["plugins"]) => list_plugins(),
+        ("POST", ["plugins", "rescan"]) => rescan_plugins(req),
+        _ => Err(ApiError::new(404, "not found")),
+    }
+}
+
+/// Parse the JSON body of the request, an empty body is parsed as the default value.
+fn body<T>(req: &HttpRequest) -> Result<T, ApiError>
+where
+    T: DeserializeOwned + Default,
+{
+    if req.body.is_empty() {
+        return Ok(T::default());
+    }
+
+    serde_json::from_slice(&req.body)
+        .map_err(|err| ApiError::new(400, format!("invalid request body: {err}")))
+}
+
+/// Find the connected client by its id or nickname.
+fn find_target(target: &str) -> Result<Client, ApiError> {
+    find_client(target).ok_or_else(|| ApiError::new(404, format!("unknown client `{target}`")))
+}
+
+fn list_clients() -> ApiResult {
+    let mut clients: Vec<Client> = CLIENTS.lock().unwrap().values().cloned().collect();
+    clients.sort_by_key(|client| client.id);
+
+    let clients: Vec<ClientInfo> = clients.iter().map(ClientInfo::from).collect();
+
+    Ok(HttpResponse::json(200, &clients))
+}
+
+fn kick(http: &HttpRequest, target: &str, req: KickRequest) -> ApiResult {
+    let client = find_target(target)?;
+
+    moderation::kick(&client, req.reason.as_deref())?;
+
+    audit::record(
+        &Actor::admin_api(http.peer_addr),
+        "kick",
+        Some(&client.display_name()),
+        req.reason.as_deref(),
+    );
+
+    Ok(HttpResponse::json(200, &json!({ "kicked": client.id })))
+}
+
+fn send(target: &str, req: MessageRequest) -> ApiResult {
+    if req.message.is_empty() {
+        return Err(ApiError::new(400, "missing message"));
+    }
+
+    let client = find_target(target)?;
+
+    client.send(&req.message)?;
+
+    Ok(HttpResponse::json(200, &json!({ "sent": client.id })))
+}
+
+fn broadcast(req: MessageRequest) -> ApiResult {
+    if req.message.is_empty() {
+        return Err(ApiError::new(400, "missing message"));
+    }
+
+    let sent = server::broadcast(&req.message);
+
+    task::block_on(history::record(None, None, &req.message));
+
+    Ok(HttpResponse::json(200, &json!({ "sent": sent })))
+}
+
+fn ban(http: &HttpRequest, req: BanRequest) -> ApiResult {
+    // connected clients are banned by their address
+    let target = match BanTarget::parse(&req.target) {
+        Ok(target) => target,
+        Err(err) => match find_client(&req.target) {
+            Some(client) => match client.peer_addr() {
+                Ok(addr) => BanTarget::Address(addr.ip().into()),
+                // e.g. clients connected using a Unix domain socket
+                Err(_) => return Err(ApiError::new(400, "client has no address")),
+            },
+            None => return Err(ApiError::new(400, err)),
+        },
+    };
+
+    let duration = req
+        .duration
+        .as_deref()
+        .map(moderation::parse_duration)
+        .transpose()
+        .map_err(|err| ApiError::new(400, err))?;
+
+    let ban = Ban {
+        target,
+        reason: req.reason.filter(|reason| !reason.is_empty()),
+        expires_at: duration.map(moderation::expires_at),
+        banned_by: ADMIN_NAME.to_string(),
+    };
+
+    moderation::ban(ban.clone())?;
+
+    audit::record(
+        &Actor::admin_api(http.peer_addr),
+        "ban",
+        Some(&ban.target.to_string()),
+        Some(&audit::duration_details(duration, ban.reason.as_deref())),
+    );
+
+    // disconnect banned clients
+    let kicked = moderation::kick_banned(&ban)?;
+
+    Ok(HttpResponse::json(
+        200,
+        &json!({
+            "target": ban.target.to_string(),
+            "expires_at": ban.expires_at,
+            "kicked": kicked,
+        }),
+    ))
+}
+
+fn list_plugins() -> ApiResult {
+    Ok(HttpResponse::json(
+        200,
+        &plugins_json(&server::plugins_manager()),
+    ))
+}
+
+fn rescan_plugins(http: &HttpRequest) -> ApiResult {
+    let plugins_manager = server::rescan_plugins()?;
+
+    audit::record(
+        &Actor::admin_api(http.peer_addr),
+        "plugins_rescan",
+        None,
+        None,
+    );
+
+    Ok(HttpResponse::json(200, &plugins_json(&plugins_manager)))
+}
+
+/// Returns loaded plugins, commands, events and authenticators.
+fn plugins_json(plugins_manager: &PluginsManager) -> serde_json::Value {
+    json!({
+        "plugins": plugins_manager
+            .plugins
+            .iter()
+            .map(|plugin| plugin.name())
+            .collect::<Vec<_>>(),
+        "commands": plugins_manager
+            .commands
+            .iter()
+            .map(|cmd| json!({
+                "name": cmd.name(),
+                "aliases": cmd.aliases(),
+                "help": cmd.help(),
+                "usage": cmd.usage(),
+                "permission": cmd.required_permission(),
+            }))
+            .collect::<Vec<_>>(),
+        "events": plugins_manager
+            .events
+            .iter()
+            .map(|event| event.event().to_string())
+            .collect::<Vec<_>>(),
+        "authenticators": plugins_manager
+            .authenticators
+            .iter()
+            .map(|authenticator| authenticator.name())
+            .collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,238 @@
+//! Audit log of security-relevant actions.
+//!
+//! Actions are appended to the [AUDIT_FILE] as JSON lines, separately from the
+//! logs of the server. Recorded actions:
+//!
+//! - `login`, `login_failed` - authentication of the clients
+//! - `kick`, `ban`, `unban`, `mute`, `unmute` - moderation
+//! - `role_grant`, `role_revoke`, `user_add`, `token_new` - permission changes
+//! - `plugin_load`, `plugins_rescan` - plugins
+//! - `admin_api` - requests to the admin API
+//!
+//! Example entry:
+//!
+//! ```json
+//! {"timestamp":1700000000,"actor":{"id":3,"name":"alice","address":"192.0.2.1:56324"},"action":"ban","target":"198.51.100.7/32","details":"spam"}
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    net::SocketAddr,
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{
+    moderation::unix_now,
+    server::{format_duration, Client},
+};
+
+/// Path to the audit log file.
+pub const AUDIT_FILE: &str = "audit.log";
+
+lazy_static! {
+    /// Lock of the [AUDIT_FILE], so the entries aren't interleaved
+    static ref AUDIT_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Who made the action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Actor {
+    /// ID of the client (`None` if the actor isn't a connected client)
+    pub id: Option<usize>,
+    /// Name of the client, `console`, `admin API` or `server`
+    pub name: String,
+    /// Address of the client
+    pub address: Option<String>,
+}
+
+impl Actor {
+    /// The client (or the admin console).
+    pub fn client(client: &Client) -> Self {
+        let address = match client.metadata.peer_credentials {
+            Some(credentials) => Some(credentials.to_string()),
+            None => client.peer_addr().ok().map(|addr| addr.to_string()),
+        };
+
+        Self {
+            id: (!client.is_console()).then_some(client.id),
+            name: client.display_name(),
+            address,
+        }
+    }
+
+    /// Caller of the admin API.
+    pub fn admin_api(addr: Option<SocketAddr>) -> Self {
+        Self {
+            id: None,
+            name: "admin API".to_string(),
+            address: addr.map(|addr| addr.to_string()),
+        }
+    }
+
+    /// The server itself (e.g. loading plugins on startup).
+    pub fn server() -> Self {
+        Self {
+            id: None,
+            name: "server".to_string(),
+            address: None,
+        }
+    }
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        match (self.id, &self.address) {
+            (Some(id), Some(address)) => write!(f, " (#{id}, {address})"),
+            (Some(id), None) => write!(f, " (#{id})"),
+            (None, Some(address)) => write!(f, " ({address})"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Entry of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix timestamp of the action in seconds
+    pub timestamp: u64,
+    /// Who made the action
+    pub actor: Actor,
+    /// Name of the action, e.g. `ban`
+    pub action: String,
+    /// Client, account, address or plugin the action was made on
+    pub target: Option<String>,
+    /// Details of the action, e.g. the reason of the ban
+    pub details: Option<String>,
+}
+
+impl AuditEntry {
+    /// Returns `true` if the action, the actor (name or id) or the target is the filter.
+    pub fn matches(&self, filter: &str) -> bool {
+        self.action == filter
+            || self.actor.name.eq_ignore_ascii_case(filter)
+            || self.actor.id.is_some_and(|id| id.to_string() == filter)
+            || self
+                .target
+                .as_ref()
+                .is_some_and(|target| target.eq_ignore_ascii_case(filter))
+    }
+}
+
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ago = Duration::from_secs(unix_now().saturating_sub(self.timestamp));
+
+        write!(
+            f,
+            "{} ago: {} {}",
+            format_duration(ago),
+            self.actor,
+            self.action
+        )?;
+
+        if let Some(target) = &self.target {
+            write!(f, " {target}")?;
+        }
+
+        if let Some(details) = &self.details {
+            write!(f, ": {details}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Append the action to the audit log. Errors are logged, so a broken audit
+/// log doesn't break the action.
+pub fn record(actor: &Actor, action: &str, target: Option<&str>, details: Option<&str>) {
+    let entry = AuditEntry {
+        timestamp: unix_now(),
+        actor: actor.clone(),
+        action: action.to_string(),
+        target: target.map(|target| target.to_string()),
+        details: details.map(|details| details.to_string()),
+    };
+
+    if let Err(err) = append(AUDIT_FILE, &entry) {
+        error!("Failed to write to the audit log: {}", err);
+    }
+}
+
+/// Returns the duration (e.g. of a ban) and the reason for the details of the entry.
+pub fn duration_details(duration: Option<Duration>, reason: Option<&str>) -> String {
+    let duration = match duration {
+        Some(duration) => format_duration(duration),
+        None => "permanent".to_string(),
+    };
+
+    match reason {
+        Some(reason) => format!("{duration}: {reason}"),
+        None => duration,
+    }
+}
+
+fn append<P>(path: P, entry: &AuditEntry) -> anyhow::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    let _lock = AUDIT_LOCK.lock().unwrap();
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+
+    Ok(())
+}
+
+/// Returns the last `limit` entries of the audit log matching the filter (see
+/// [AuditEntry::matches]), the oldest first. The log is read line by line,
+/// only the last `limit` entries are kept in memory.
+pub fn query(filter: Option<&str>, limit: usize) -> anyhow::Result<Vec<AuditEntry>> {
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    // entries are appended with a single write, so the log can be read
+    // without the lock (a partially written last line is skipped)
+    let file = match File::open(AUDIT_FILE) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut entries = VecDeque::with_capacity(limit);
+
+    for line in BufReader::new(file).lines() {
+        let Ok(entry) = serde_json::from_str::<AuditEntry>(&line?) else {
+            continue;
+        };
+
+        if filter.is_some_and(|filter| !entry.matches(filter)) {
+            continue;
+        }
+
+        if entries.len() == limit {
+            entries.pop_front();
+        }
+
+        entries.push_back(entry);
+    }
+
+    Ok(entries.into())
+}
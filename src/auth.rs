@@ -0,0 +1,305 @@
+//! Authentication of the clients.
+//!
+//! Clients can authenticate using the `/login` command or, for WebSocket
+//! clients, using the `Authorization` header (`Basic` or `Bearer`) or the
+//! `token` query parameter of the handshake request.
+//!
+//! Credentials are verified by the registered [Authenticator]s in order. The
+//! built-in authenticator uses accounts from the [USERS_FILE] with salted
+//! PBKDF2-SHA256 password hashes.
+
+use std::{collections::HashMap, fs, path::Path, sync::RwLock};
+
+use anyhow::anyhow;
+use hmac::Hmac;
+use lazy_static::lazy_static;
+use rand::{distributions::Alphanumeric, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::{
+    audit::{self, Actor},
+    moderation,
+    plugins::prelude::*,
+};
+
+/// Path to the file with user accounts.
+pub const USERS_FILE: &str = "users.toml";
+
+/// Number of PBKDF2 iterations used for new password hashes.
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+lazy_static! {
+    /// User accounts loaded from the [USERS_FILE]
+    pub static ref USERS: RwLock<UserStore> =
+        RwLock::new(UserStore::load(USERS_FILE).expect("failed to load users"));
+}
+
+/// Register default authenticators
+pub fn register_authenticators() -> Vec<Box<dyn Authenticator>> {
+    vec![Box::new(FileAuthenticator)]
+}
+
+/// File-backed user accounts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserStore {
+    /// Accounts by their names.
+    pub users: HashMap<String, User>,
+}
+
+/// User account stored in the [UserStore].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct User {
+    /// Password hash in format `pbkdf2-sha256$<rounds>$<salt>$<hash>`.
+    pub password: String,
+    /// SHA-256 hashes of the access tokens.
+    pub tokens: Vec<String>,
+    /// Roles given to the client after authentication.
+    pub roles: Vec<String>,
+}
+
+impl UserStore {
+    /// Load the accounts from the file, if the file doesn't exists, returns an empty store.
+    pub fn load<P>(path: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Save the accounts to the file.
+    pub fn save<P>(&self, path: P) -> anyhow::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Add a new account. Returns an error if the account already exists.
+    pub fn add_user(
+        &mut self,
+        username: &str,
+        password: &str,
+        roles: Vec<String>,
+    ) -> anyhow::Result<()> {
+        if self.users.contains_key(username) {
+            return Err(anyhow!("user `{username}` already exists"));
+        }
+
+        let user = User {
+            password: hash_password(password),
+            tokens: Vec::new(),
+            roles,
+        };
+
+        self.users.insert(username.to_string(), user);
+
+        Ok(())
+    }
+
+    /// Generate a new access token for the account.
+    pub fn new_token(&mut self, username: &str) -> anyhow::Result<String> {
+        let user = self
+            .users
+            .get_mut(username)
+            .ok_or_else(|| anyhow!("user `{username}` not found"))?;
+
+        let token = generate_token();
+
+        user.tokens.push(hash_token(&token));
+
+        Ok(token)
+    }
+
+    /// Verify the credentials and returns the account.
+    pub fn verify(&self, credentials: &Credentials) -> Option<Account> {
+        let (name, user) = match credentials {
+            Credentials::Password { username, password } => {
+                let user = self.users.get(username)?;
+
+                if !verify_password(&user.password, password) {
+                    return None;
+                }
+
+                (username, user)
+            },
+            Credentials::Token(token) => {
+                let hash = hash_token(token);
+
+                self.users
+                    .iter()
+                    .find(|(_name, user)| user.tokens.contains(&hash))?
+            },
+        };
+
+        Some(Account {
+            name: name.clone(),
+            roles: user.roles.clone(),
+        })
+    }
+}
+
+/// Authenticator using accounts from the [USERS_FILE].
+pub struct FileAuthenticator;
+
+#[async_trait]
+impl Authenticator for FileAuthenticator {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn authenticate(&self, credentials: &Credentials) -> anyhow::Result<Option<Account>> {
+        Ok(USERS.read().unwrap().verify(credentials))
+    }
+}
+
+/// Authenticate the client using registered authenticators. Returns `false` if the credentials are invalid.
+pub async fn authenticate(client: &Client, credentials: &Credentials) -> anyhow::Result<bool> {
+    for authenticator in client.plugins_manager().authenticators.iter() {
+        if let Some(account) = authenticator.authenticate(credentials).await? {
+            if moderation::is_account_banned(&account.name) {
+                info!("Rejected banned account `{}`", account.name);
+
+                audit::record(
+                    &Actor::client(client),
+                    "login_failed",
+                    Some(&account.name),
+                    Some("account is banned"),
+                );
+
+                return Ok(false);
+            }
+
+            info!(
+                "Authenticated as `{}` by `{}` authenticator",
+                account.name,
+                authenticator.name()
+            );
+
+            for role in account.roles.iter() {
+                client.grant_role(role);
+            }
+
+            audit::record(
+                &Actor::client(client),
+                "login",
+                Some(&account.name),
+                Some(&format!("`{}` authenticator", authenticator.name())),
+            );
+
+            *client.account.lock().unwrap() = Some(account.name);
+
+            return Ok(true);
+        }
+    }
+
+    let username = match credentials {
+        Credentials::Password { username, .. } => Some(username.as_str()),
+        Credentials::Token(_) => None,
+    };
+
+    audit::record(
+        &Actor::client(client),
+        "login_failed",
+        username,
+        Some("invalid credentials"),
+    );
+
+    Ok(false)
+}
+
+/// Parse credentials from the value of the HTTP `Authorization` header.
+pub fn parse_authorization(value: &str) -> Option<Credentials> {
+    let (scheme, value) = value.trim().split_once(' ')?;
+
+    if scheme.eq_ignore_ascii_case("bearer") {
+        return Some(Credentials::Token(value.trim().to_string()));
+    }
+
+    if scheme.eq_ignore_ascii_case("basic") {
+        let decoded = base64::decode(value.trim()).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+
+        let (username, password) = decoded.split_once(':')?;
+
+        return Some(Credentials::Password {
+            username: username.to_string(),
+            password: password.to_string(),
+        });
+    }
+
+    None
+}
+
+/// Generate a random alphanumeric token.
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Hash the password with a random salt.
+pub fn hash_password(password: &str) -> String {
+    let mut salt = [0; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+
+    let hash = pbkdf2_sha256(password, &salt, PBKDF2_ROUNDS);
+
+    format!(
+        "pbkdf2-sha256${PBKDF2_ROUNDS}${}${}",
+        hex::encode(salt),
+        hex::encode(hash)
+    )
+}
+
+/// Verify the password using the hash created by [hash_password].
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    let parts: Vec<&str> = hash.split('$').collect();
+
+    let (rounds, salt, expected) = match parts.as_slice() {
+        ["pbkdf2-sha256", rounds, salt, hash] => (rounds, salt, hash),
+        _ => return false,
+    };
+
+    let (Ok(rounds), Ok(salt), Ok(expected)) =
+        (rounds.parse(), hex::decode(salt), hex::decode(expected))
+    else {
+        return false;
+    };
+
+    let hash = pbkdf2_sha256(password, &salt, rounds);
+
+    constant_time_eq(&hash, &expected)
+}
+
+/// Compare the secrets in constant time (only the length can leak).
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn pbkdf2_sha256(password: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut hash = [0; 32];
+
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, rounds, &mut hash);
+
+    hash
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,57 @@
+use crate::{audit, plugins::prelude::*};
+
+/// Number of entries shown by default
+const DEFAULT_LIMIT: usize = 20;
+
+/// Max number of entries shown
+const MAX_LIMIT: usize = 200;
+
+pub struct Audit;
+
+#[async_trait]
+impl Command for Audit {
+    fn name(&self) -> &'static str {
+        "/audit"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Show the last entries of the audit log"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/audit [action|client id|name|target] [count]"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("audit")
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let (filter, limit) = match args.as_slice() {
+            [] => (None, DEFAULT_LIMIT),
+            [arg] => match arg.parse() {
+                Ok(count) => (None, count),
+                Err(_) => (Some(*arg), DEFAULT_LIMIT),
+            },
+            [filter, count] => match count.parse() {
+                Ok(count) => (Some(*filter), count),
+                Err(_) => return client.send(format!("Usage: {}", self.usage())),
+            },
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        let entries = audit::query(filter, limit.min(MAX_LIMIT))?;
+
+        if entries.is_empty() {
+            return client.send("No audit entries");
+        }
+
+        let msg: Vec<String> = entries.iter().map(|entry| entry.to_string()).collect();
+
+        client.send(msg.join("\n"))
+    }
+}
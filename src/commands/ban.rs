@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use crate::{
+    audit::{self, Actor},
+    moderation::{self, BanTarget},
+    plugins::prelude::*,
+    server::format_duration,
+};
+
+pub struct Ban;
+
+#[async_trait]
+impl Command for Ban {
+    fn name(&self) -> &'static str {
+        "/ban"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Ban an address (IP or CIDR), account or connected client"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/ban <ip|cidr|account:name|client id|nick> [duration e.g. 30m, 2d] [reason]"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("moderation.ban")
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        if args.is_empty() {
+            return client.send(format!("Usage: {}", self.usage()));
+        }
+
+        // connected clients are banned by their address
+        let target = match BanTarget::parse(args[0]) {
+            Ok(target) => target,
+            Err(err) => match find_client(args[0]) {
+                Some(target) => match target.peer_addr() {
+                    Ok(addr) => BanTarget::Address(addr.ip().into()),
+                    Err(err) => return client.send(err),
+                },
+                None => return client.send(err),
+            },
+        };
+
+        let mut args = &args[1..];
+
+        let duration: Option<Duration> =
+            match args.first().map(|arg| moderation::parse_duration(arg)) {
+                Some(Ok(duration)) => {
+                    args = &args[1..];
+                    Some(duration)
+                },
+                _ => None,
+            };
+
+        let reason = Some(args.join(" ")).filter(|reason| !reason.is_empty());
+
+        // plugins can block the ban by returning an error
+        client
+            .run_events(
+                EventType::OnBan,
+                EventData::Moderation {
+                    target: target.to_string(),
+                    reason: reason.clone(),
+                    duration,
+                },
+            )
+            .await?;
+
+        let ban = moderation::Ban {
+            target,
+            reason,
+            expires_at: duration.map(moderation::expires_at),
+            banned_by: client.display_name(),
+        };
+
+        moderation::ban(ban.clone())?;
+
+        audit::record(
+            &Actor::client(client),
+            "ban",
+            Some(&ban.target.to_string()),
+            Some(&audit::duration_details(duration, ban.reason.as_deref())),
+        );
+
+        // disconnect banned clients
+        moderation::kick_banned(&ban)?;
+
+        match duration {
+            Some(duration) => client.send(format!(
+                "Banned {} for {}",
+                ban.target,
+                format_duration(duration)
+            )),
+            None => client.send(format!("Banned {}", ban.target)),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{moderation::BANS, plugins::prelude::*};
+
+pub struct Bans;
+
+#[async_trait]
+impl Command for Bans {
+    fn name(&self) -> &'static str {
+        "/bans"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["/banlist"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Show list of active bans"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/bans"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("moderation.ban")
+    }
+
+    async fn execute(&self, client: &Client, _args: Vec<&str>) -> anyhow::Result<()> {
+        let bans: Vec<String> = BANS
+            .read()
+            .unwrap()
+            .bans
+            .iter()
+            .filter(|ban| !ban.is_expired())
+            .map(|ban| ban.to_string())
+            .collect();
+
+        if bans.is_empty() {
+            client.send("No bans")
+        } else {
+            client.send(bans.join("\n"))
+        }
+    }
+}
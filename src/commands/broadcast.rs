@@ -1,6 +1,4 @@
-use async_std::task;
-
-use crate::{plugins::prelude::*, CLIENTS};
+use crate::{history, plugins::prelude::*, server::broadcast};
 
 pub struct Broadcast;
 
@@ -22,32 +20,25 @@ impl Command for Broadcast {
         "/broadcast <message>"
     }
 
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("broadcast")
+    }
+
     async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
         if args.is_empty() || args.join(" ").is_empty() {
             client.send("Missing message")?;
             return Ok(());
         }
 
-        let msg = args.join(" ");
-
-        let mut children = Vec::new();
+        if client.is_muted() {
+            return client.send("You are muted");
+        }
 
-        // send message to all connected clients
-        for (_i, client) in CLIENTS.lock().unwrap().clone() {
-            let msg = msg.clone();
-            let child = task::spawn(async move {
-                client
-                    .send(msg)
-                    .expect("failed to send broadcast message to client")
-            });
+        let msg = format!("[{}] {}", client.display_name(), args.join(" "));
 
-            children.push(child);
-        }
+        broadcast(&msg);
 
-        // wait for all task to complete
-        for child in children {
-            child.await;
-        }
+        history::record(Some(client), None, &msg).await;
 
         Ok(())
     }
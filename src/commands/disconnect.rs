@@ -1,4 +1,4 @@
-use crate::plugins::prelude::*;
+use crate::{plugins::prelude::*, server::end_session};
 
 pub struct Disconnect;
 
@@ -20,7 +20,18 @@ impl Command for Disconnect {
         "/disconnect"
     }
 
+    fn requires_auth(&self) -> bool {
+        false
+    }
+
     async fn execute(&self, client: &Client, _args: Vec<&str>) -> anyhow::Result<()> {
+        if client.is_console() {
+            return client.send("The console can't be disconnected, close its input instead");
+        }
+
+        // closing the connection on purpose doesn't leave a session to resume
+        end_session(client.id);
+
         client.close()
     }
 }
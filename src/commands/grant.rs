@@ -0,0 +1,60 @@
+use crate::{
+    audit::{self, Actor},
+    permissions::POLICY,
+    plugins::prelude::*,
+};
+
+pub struct Grant;
+
+#[async_trait]
+impl Command for Grant {
+    fn name(&self) -> &'static str {
+        "/grant"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Give a role to the client"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/grant <client id|nick> <role>"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("roles.manage")
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        if args.len() != 2 {
+            return client.send(format!("Usage: {}", self.usage()));
+        }
+
+        let role = args[1];
+
+        if !POLICY.read().unwrap().role_exists(role) {
+            return client.send(format!("Unknown role `{role}`"));
+        }
+
+        let target = match find_client(args[0]) {
+            Some(target) => target,
+            None => return client.send("Client not found"),
+        };
+
+        if target.grant_role(role) {
+            audit::record(
+                &Actor::client(client),
+                "role_grant",
+                Some(&target.display_name()),
+                Some(role),
+            );
+
+            client.send(format!("Granted role `{role}` to client {}", target.id))
+        } else {
+            client.send(format!("Client {} already has role `{role}`", target.id))
+        }
+    }
+}
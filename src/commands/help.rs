@@ -20,10 +20,14 @@ impl Command for Help {
         "/help"
     }
 
+    fn requires_auth(&self) -> bool {
+        false
+    }
+
     async fn execute(&self, client: &Client, _args: Vec<&str>) -> anyhow::Result<()> {
         let mut msg = Vec::new();
 
-        for cmd in client.plugins_manager.commands.iter() {
+        for cmd in client.plugins_manager().commands.iter() {
             let aliases = cmd.aliases();
 
             let aliases = if !aliases.is_empty() {
@@ -0,0 +1,61 @@
+use crate::{history, plugins::prelude::*, rooms};
+
+/// Number of messages shown by default
+const DEFAULT_LIMIT: usize = 20;
+
+pub struct History;
+
+#[async_trait]
+impl Command for History {
+    fn name(&self) -> &'static str {
+        "/history"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Show the last broadcasts or messages of the room"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/history [room] [count]"
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let (room, limit) = match args.as_slice() {
+            [] => (None, DEFAULT_LIMIT),
+            [arg] => match arg.parse() {
+                Ok(count) => (None, count),
+                Err(_) => (Some(*arg), DEFAULT_LIMIT),
+            },
+            [room, count] => match count.parse() {
+                Ok(count) => (Some(*room), count),
+                Err(_) => return client.send(format!("Usage: {}", self.usage())),
+            },
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        // only members can read messages of the room
+        let room = match room {
+            Some(name) => match rooms::get_room(name) {
+                Some(room) if room.members.contains(&client.id) => Some(room.id),
+                _ => return client.send(format!("You are not in room `{name}`")),
+            },
+            None => None,
+        };
+
+        let entries = history::recent(room.as_deref(), limit).await?;
+
+        if entries.is_empty() {
+            return client.send("No messages in the history");
+        }
+
+        for entry in entries {
+            client.send(entry.to_string())?;
+        }
+
+        Ok(())
+    }
+}
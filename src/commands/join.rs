@@ -0,0 +1,45 @@
+use crate::{plugins::prelude::*, rooms};
+
+pub struct Join;
+
+#[async_trait]
+impl Command for Join {
+    fn name(&self) -> &'static str {
+        "/join"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Join the room (the room is created if it doesn't exist)"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/join <room> [password]"
+    }
+
+    fn has_credentials(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let (name, password) = match args.as_slice() {
+            [name] => (name, None),
+            [name, password] => (name, Some(*password)),
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        if let Err(err) = rooms::join(client, name, password) {
+            return client.send(err);
+        }
+
+        let room = rooms::room_name(name)?;
+
+        rooms::send_to_room(
+            &room,
+            format!("[{room}] {} joined the room", client.display_name()),
+        )
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{
+    audit::{self, Actor},
+    moderation,
+    plugins::prelude::*,
+};
+
+pub struct Kick;
+
+#[async_trait]
+impl Command for Kick {
+    fn name(&self) -> &'static str {
+        "/kick"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Disconnect the client"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/kick <client id|nick> [reason]"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("moderation.kick")
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        if args.is_empty() {
+            return client.send(format!("Usage: {}", self.usage()));
+        }
+
+        let target = match find_client(args[0]) {
+            Some(target) => target,
+            None => return client.send(format!("Unknown client `{}`", args[0])),
+        };
+
+        let reason = Some(args[1..].join(" ")).filter(|reason| !reason.is_empty());
+
+        // plugins can block the kick by returning an error
+        client
+            .run_events(
+                EventType::OnKick,
+                EventData::Moderation {
+                    target: target.id.to_string(),
+                    reason: reason.clone(),
+                    duration: None,
+                },
+            )
+            .await?;
+
+        moderation::kick(&target, reason.as_deref())?;
+
+        audit::record(
+            &Actor::client(client),
+            "kick",
+            Some(&target.display_name()),
+            reason.as_deref(),
+        );
+
+        client.send(format!("Kicked {}", target.display_name()))
+    }
+}
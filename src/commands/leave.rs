@@ -0,0 +1,47 @@
+use crate::{plugins::prelude::*, rooms};
+
+pub struct Leave;
+
+#[async_trait]
+impl Command for Leave {
+    fn name(&self) -> &'static str {
+        "/leave"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["/part"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Leave the room"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/leave <room>"
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let name = match args.as_slice() {
+            [name] => name,
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        if let Err(err) = rooms::leave(client.id, name) {
+            return client.send(err);
+        }
+
+        let room = rooms::room_name(name)?;
+
+        client.send(format!("Left room `{room}`"))?;
+
+        // the room is deleted when the last client leaves it
+        if rooms::get_room(&room).is_some() {
+            rooms::send_to_room(
+                &room,
+                format!("[{room}] {} left the room", client.display_name()),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{plugins::prelude::*, rooms, server::format_duration, CLIENTS};
+
+pub struct List;
+
+#[async_trait]
+impl Command for List {
+    fn name(&self) -> &'static str {
+        "/list"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["/who"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Show list of connected clients"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/list"
+    }
+
+    async fn execute(&self, client: &Client, _args: Vec<&str>) -> anyhow::Result<()> {
+        let mut clients: Vec<Client> = CLIENTS.lock().unwrap().values().cloned().collect();
+        clients.sort_by_key(|client| client.id);
+
+        let msg: Vec<String> = clients
+            .iter()
+            .map(|client| {
+                let rooms = rooms::client_rooms(client.id);
+
+                format!(
+                    "{id} {name} ({transport}) rooms: {rooms} idle: {idle}",
+                    id = client.id,
+                    name = client.display_name(),
+                    transport = client.metadata.transport,
+                    rooms = if rooms.is_empty() {
+                        "none".to_string()
+                    } else {
+                        rooms.join(", ")
+                    },
+                    idle = format_duration(client.metadata.idle_for()),
+                )
+            })
+            .collect();
+
+        client.send(msg.join("\n"))
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{auth, plugins::prelude::*};
+
+pub struct Login;
+
+#[async_trait]
+impl Command for Login {
+    fn name(&self) -> &'static str {
+        "/login"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["/auth"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Authenticate using username and password or an access token"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/login <username> <password> | /login <token>"
+    }
+
+    fn requires_auth(&self) -> bool {
+        false
+    }
+
+    fn has_credentials(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        if client.is_authenticated() {
+            return client.send("Already authenticated");
+        }
+
+        let credentials = match args.as_slice() {
+            [token] => Credentials::Token(token.to_string()),
+            [username, password] => Credentials::Password {
+                username: username.to_string(),
+                password: password.to_string(),
+            },
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        if auth::authenticate(client, &credentials).await? {
+            client.send("Authenticated")
+        } else {
+            client.send("authentication failed")
+        }
+    }
+}
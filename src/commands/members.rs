@@ -0,0 +1,49 @@
+use crate::{plugins::prelude::*, rooms};
+
+pub struct Members;
+
+#[async_trait]
+impl Command for Members {
+    fn name(&self) -> &'static str {
+        "/members"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Show members of the room"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/members <room>"
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let name = match args.as_slice() {
+            [name] => name,
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        let room = match rooms::get_room(name) {
+            Some(room) => room,
+            None => return client.send(format!("Room `{name}` doesn't exist")),
+        };
+
+        let members: Vec<String> = room
+            .members
+            .iter()
+            .filter_map(|id| get_client(*id))
+            .map(|member| {
+                if room.owner == Some(member.id) {
+                    format!("{} (owner)", member.display_name())
+                } else {
+                    member.display_name()
+                }
+            })
+            .collect();
+
+        client.send(members.join(", "))
+    }
+}
@@ -1,25 +1,103 @@
 //! Default servers commands.
 //!
 //! List of commands:
+//! - /audit
+//! - /ban
+//! - /bans
 //! - /broadcast
 //! - /disconnect
+//! - /grant
 //! - /help
+//! - /history
 //! - /id
+//! - /join
+//! - /kick
+//! - /leave
+//! - /list
+//! - /login
+//! - /members
+//! - /msg
+//! - /mute
+//! - /newtoken
+//! - /nick
+//! - /revoke
+//! - /roles
+//! - /roompass
+//! - /rooms
+//! - /say
+//! - /unban
+//! - /unmute
+//! - /useradd
+//! - /whois
 
+mod audit;
+mod ban;
+mod bans;
 mod broadcast;
 mod disconnect;
+mod grant;
 mod help;
+mod history;
 mod id;
+mod join;
+mod kick;
+mod leave;
+mod list;
+mod login;
+mod members;
+mod msg;
+mod mute;
+mod newtoken;
+mod nick;
+mod revoke;
+mod roles;
+mod roompass;
+mod rooms;
+mod say;
+mod unban;
+mod unmute;
+mod useradd;
+mod whois;
 
-use self::{broadcast::Broadcast, disconnect::Disconnect, help::Help, id::Id};
+use self::{
+    audit::Audit, ban::Ban, bans::Bans, broadcast::Broadcast, disconnect::Disconnect, grant::Grant,
+    help::Help, history::History, id::Id, join::Join, kick::Kick, leave::Leave, list::List,
+    login::Login, members::Members, msg::Msg, mute::Mute, newtoken::NewToken, nick::Nick,
+    revoke::Revoke, roles::Roles, roompass::RoomPass, rooms::Rooms, say::Say, unban::Unban,
+    unmute::Unmute, useradd::UserAdd, whois::Whois,
+};
 use crate::plugins::prelude::*;
 
 /// Register default commands
 pub fn register_commands() -> Vec<Box<dyn Command>> {
     vec![
+        Box::new(Audit),
+        Box::new(Ban),
+        Box::new(Bans),
         Box::new(Broadcast),
         Box::new(Disconnect),
+        Box::new(Grant),
         Box::new(Help),
+        Box::new(History),
         Box::new(Id),
+        Box::new(Join),
+        Box::new(Kick),
+        Box::new(Leave),
+        Box::new(List),
+        Box::new(Login),
+        Box::new(Members),
+        Box::new(Msg),
+        Box::new(Mute),
+        Box::new(NewToken),
+        Box::new(Nick),
+        Box::new(Revoke),
+        Box::new(Roles),
+        Box::new(RoomPass),
+        Box::new(Rooms),
+        Box::new(Say),
+        Box::new(Unban),
+        Box::new(Unmute),
+        Box::new(UserAdd),
+        Box::new(Whois),
     ]
 }
@@ -0,0 +1,66 @@
+use crate::{plugins::prelude::*, server::is_session_detached, CLIENT_NEXT};
+
+pub struct Msg;
+
+#[async_trait]
+impl Command for Msg {
+    fn name(&self) -> &'static str {
+        "/msg"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["/whisper"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Send a private message to the client"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/msg <client id|nick> <message>"
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        if args.len() < 2 {
+            return client.send(format!("Usage: {}", self.usage()));
+        }
+
+        if client.is_muted() {
+            return client.send("You are muted");
+        }
+
+        let target_name = args[0];
+        let msg = args[1..].join(" ");
+
+        let target = match find_client(target_name) {
+            Some(target) => target,
+            None => {
+                // messages to clients with a detached session are delivered on resume
+                if let Some(id) = target_name
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|id| is_session_detached(*id))
+                {
+                    send_to(id, format!("[{} -> you] {}", client.display_name(), msg))?;
+
+                    return client.send(format!("[you -> #{id}] {msg} (queued)"));
+                }
+
+                // ids lower than the next id were used by clients that are now disconnected
+                let offline = target_name
+                    .parse::<usize>()
+                    .is_ok_and(|id| id < *CLIENT_NEXT.lock().unwrap());
+
+                return if offline {
+                    client.send(format!("Client {target_name} is offline"))
+                } else {
+                    client.send(format!("Unknown client `{target_name}`"))
+                };
+            },
+        };
+
+        target.send(format!("[{} -> you] {}", client.display_name(), msg))?;
+
+        client.send(format!("[you -> {}] {}", target.display_name(), msg))
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{
+    audit::{self, Actor},
+    moderation,
+    plugins::prelude::*,
+    server::format_duration,
+};
+
+pub struct Mute;
+
+#[async_trait]
+impl Command for Mute {
+    fn name(&self) -> &'static str {
+        "/mute"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Prevent the client from sending messages to other clients"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/mute <client id|nick> [duration e.g. 30m, 2d] [reason]"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("moderation.mute")
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        if args.is_empty() {
+            return client.send(format!("Usage: {}", self.usage()));
+        }
+
+        let target = match find_client(args[0]) {
+            Some(target) => target,
+            None => return client.send(format!("Unknown client `{}`", args[0])),
+        };
+
+        let mut args = &args[1..];
+
+        let duration = match args.first().map(|arg| moderation::parse_duration(arg)) {
+            Some(Ok(duration)) => {
+                args = &args[1..];
+                Some(duration)
+            },
+            _ => None,
+        };
+
+        let reason = Some(args.join(" ")).filter(|reason| !reason.is_empty());
+
+        // plugins can block the mute by returning an error
+        client
+            .run_events(
+                EventType::OnMute,
+                EventData::Moderation {
+                    target: target.id.to_string(),
+                    reason: reason.clone(),
+                    duration,
+                },
+            )
+            .await?;
+
+        target.mute(duration);
+
+        audit::record(
+            &Actor::client(client),
+            "mute",
+            Some(&target.display_name()),
+            Some(&audit::duration_details(duration, reason.as_deref())),
+        );
+
+        let duration = duration
+            .map(|duration| format!(" for {}", format_duration(duration)))
+            .unwrap_or_default();
+
+        match &reason {
+            Some(reason) => target.send(format!("You have been muted{duration}: {reason}"))?,
+            None => target.send(format!("You have been muted{duration}"))?,
+        }
+
+        client.send(format!("Muted {}{duration}", target.display_name()))
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    audit::{self, Actor},
+    auth::{USERS, USERS_FILE},
+    plugins::prelude::*,
+};
+
+pub struct NewToken;
+
+#[async_trait]
+impl Command for NewToken {
+    fn name(&self) -> &'static str {
+        "/newtoken"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Generate a new access token for your account"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/newtoken"
+    }
+
+    async fn execute(&self, client: &Client, _args: Vec<&str>) -> anyhow::Result<()> {
+        let account = client.account.lock().unwrap().clone();
+
+        let account = match account {
+            Some(account) => account,
+            None => return client.send("authentication required"),
+        };
+
+        let mut users = USERS.write().unwrap();
+
+        let token = users.new_token(&account)?;
+
+        users.save(USERS_FILE)?;
+
+        audit::record(&Actor::client(client), "token_new", Some(&account), None);
+
+        client.send(format!("Token: {token}"))
+    }
+}
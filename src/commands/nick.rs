@@ -0,0 +1,35 @@
+use crate::plugins::prelude::*;
+
+pub struct Nick;
+
+#[async_trait]
+impl Command for Nick {
+    fn name(&self) -> &'static str {
+        "/nick"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["/name"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Show or change your nickname"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/nick [nickname]"
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let nick = match args.as_slice() {
+            [] => return client.send(client.display_name()),
+            [nick] => nick,
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        match client.set_nick(nick).await {
+            Ok(()) => client.send(format!("Nickname changed to `{nick}`")),
+            Err(err) => client.send(err),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    audit::{self, Actor},
+    plugins::prelude::*,
+};
+
+pub struct Revoke;
+
+#[async_trait]
+impl Command for Revoke {
+    fn name(&self) -> &'static str {
+        "/revoke"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Take a role from the client"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/revoke <client id|nick> <role>"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("roles.manage")
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        if args.len() != 2 {
+            return client.send(format!("Usage: {}", self.usage()));
+        }
+
+        let role = args[1];
+
+        let target = match find_client(args[0]) {
+            Some(target) => target,
+            None => return client.send("Client not found"),
+        };
+
+        if target.revoke_role(role) {
+            audit::record(
+                &Actor::client(client),
+                "role_revoke",
+                Some(&target.display_name()),
+                Some(role),
+            );
+
+            client.send(format!("Revoked role `{role}` from client {}", target.id))
+        } else {
+            client.send(format!("Client {} doesn't have role `{role}`", target.id))
+        }
+    }
+}
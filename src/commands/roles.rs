@@ -0,0 +1,45 @@
+use crate::plugins::prelude::*;
+
+pub struct Roles;
+
+#[async_trait]
+impl Command for Roles {
+    fn name(&self) -> &'static str {
+        "/roles"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Show roles of the client"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/roles [client id|nick]"
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        // roles of other clients are visible only for clients who can manage them
+        let target = match args.first() {
+            Some(_) if !client.has_permission("roles.manage") => {
+                return client.send("permission denied")
+            },
+            Some(id) => match find_client(id) {
+                Some(target) => target,
+                None => return client.send("Client not found"),
+            },
+            None => client.clone(),
+        };
+
+        let mut roles: Vec<String> = target.roles.lock().unwrap().iter().cloned().collect();
+        roles.sort();
+
+        if roles.is_empty() {
+            client.send("none")
+        } else {
+            client.send(roles.join(", "))
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{plugins::prelude::*, rooms};
+
+pub struct RoomPass;
+
+#[async_trait]
+impl Command for RoomPass {
+    fn name(&self) -> &'static str {
+        "/roompass"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Set or remove the password of your room"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/roompass <room> [password]"
+    }
+
+    fn has_credentials(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let (name, password) = match args.as_slice() {
+            [name] => (name, None),
+            [name, password] => (name, Some(*password)),
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        match rooms::set_password(client.id, name, password) {
+            Ok(()) if password.is_some() => client.send("Password set"),
+            Ok(()) => client.send("Password removed"),
+            Err(err) => client.send(err),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{plugins::prelude::*, rooms};
+
+pub struct Rooms;
+
+#[async_trait]
+impl Command for Rooms {
+    fn name(&self) -> &'static str {
+        "/rooms"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["/channels"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Show list of rooms"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/rooms"
+    }
+
+    async fn execute(&self, client: &Client, _args: Vec<&str>) -> anyhow::Result<()> {
+        let rooms = rooms::rooms();
+
+        if rooms.is_empty() {
+            return client.send("No rooms");
+        }
+
+        let msg: Vec<String> = rooms
+            .iter()
+            .map(|room| {
+                format!(
+                    "{name} ({members} members{password})",
+                    name = room.name,
+                    members = room.members.len(),
+                    password = if room.has_password() {
+                        ", password protected"
+                    } else {
+                        ""
+                    },
+                )
+            })
+            .collect();
+
+        client.send(msg.join("\n"))
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{history, plugins::prelude::*, rooms};
+
+pub struct Say;
+
+#[async_trait]
+impl Command for Say {
+    fn name(&self) -> &'static str {
+        "/say"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Send message to all clients in the room"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/say <room> <message>"
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        if args.len() < 2 {
+            return client.send(format!("Usage: {}", self.usage()));
+        }
+
+        if client.is_muted() {
+            return client.send("You are muted");
+        }
+
+        let room = match rooms::get_room(args[0]) {
+            Some(room) if room.members.contains(&client.id) => room,
+            _ => return client.send(format!("You are not in room `{}`", args[0])),
+        };
+
+        let msg = format!(
+            "[{}] [{}] {}",
+            room.name,
+            client.display_name(),
+            args[1..].join(" ")
+        );
+
+        rooms::send_to_room(&room.name, &msg)?;
+
+        history::record(Some(client), Some(&room.id), &msg).await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{
+    audit::{self, Actor},
+    moderation::{self, BanTarget},
+    plugins::prelude::*,
+};
+
+pub struct Unban;
+
+#[async_trait]
+impl Command for Unban {
+    fn name(&self) -> &'static str {
+        "/unban"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Remove the ban of an address or account"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/unban <ip|cidr|account:name>"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("moderation.ban")
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let target = match args.as_slice() {
+            [target] => match BanTarget::parse(target) {
+                Ok(target) => target,
+                Err(err) => return client.send(err),
+            },
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        if moderation::unban(&target)? {
+            audit::record(
+                &Actor::client(client),
+                "unban",
+                Some(&target.to_string()),
+                None,
+            );
+
+            client.send(format!("Unbanned {target}"))
+        } else {
+            client.send(format!("{target} is not banned"))
+        }
+    }
+}
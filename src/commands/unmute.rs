@@ -0,0 +1,53 @@
+use crate::{
+    audit::{self, Actor},
+    plugins::prelude::*,
+};
+
+pub struct Unmute;
+
+#[async_trait]
+impl Command for Unmute {
+    fn name(&self) -> &'static str {
+        "/unmute"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Allow the muted client to send messages again"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/unmute <client id|nick>"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("moderation.mute")
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let target = match args.as_slice() {
+            [target] => match find_client(target) {
+                Some(target) => target,
+                None => return client.send(format!("Unknown client `{target}`")),
+            },
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        if target.unmute() {
+            audit::record(
+                &Actor::client(client),
+                "unmute",
+                Some(&target.display_name()),
+                None,
+            );
+
+            target.send("You have been unmuted")?;
+            client.send(format!("Unmuted {}", target.display_name()))
+        } else {
+            client.send(format!("{} is not muted", target.display_name()))
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{
+    audit::{self, Actor},
+    auth::{USERS, USERS_FILE},
+    permissions::POLICY,
+    plugins::prelude::*,
+};
+
+pub struct UserAdd;
+
+#[async_trait]
+impl Command for UserAdd {
+    fn name(&self) -> &'static str {
+        "/useradd"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Create a new user account"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/useradd <username> <password> [roles...]"
+    }
+
+    fn required_permission(&self) -> Option<&'static str> {
+        Some("users.manage")
+    }
+
+    // the permission is enough to create the first account, e.g. by a client with roles
+    // given by the address in the permissions policy
+    fn requires_auth(&self) -> bool {
+        false
+    }
+
+    fn has_credentials(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        if args.len() < 2 {
+            return client.send(format!("Usage: {}", self.usage()));
+        }
+
+        let roles: Vec<String> = args[2..].iter().map(|role| role.to_string()).collect();
+
+        if let Some(role) = roles
+            .iter()
+            .find(|role| !POLICY.read().unwrap().role_exists(role))
+        {
+            return client.send(format!("Unknown role `{role}`"));
+        }
+
+        let mut users = USERS.write().unwrap();
+
+        if let Err(err) = users.add_user(args[0], args[1], roles) {
+            return client.send(err);
+        }
+
+        users.save(USERS_FILE)?;
+
+        audit::record(
+            &Actor::client(client),
+            "user_add",
+            Some(args[0]),
+            Some(&format!("roles: {}", args[2..].join(", "))),
+        );
+
+        client.send(format!("Created user `{}`", args[0]))
+    }
+}
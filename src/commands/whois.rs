@@ -0,0 +1,97 @@
+use crate::{plugins::prelude::*, rooms, server::format_duration};
+
+pub struct Whois;
+
+#[async_trait]
+impl Command for Whois {
+    fn name(&self) -> &'static str {
+        "/whois"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn help(&self) -> &'static str {
+        "Show information about the client"
+    }
+
+    fn usage(&self) -> &'static str {
+        "/whois <client id|nick>"
+    }
+
+    async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()> {
+        let target = match args.as_slice() {
+            [target] => match find_client(target) {
+                Some(target) => target,
+                None => return client.send(format!("Unknown client `{target}`")),
+            },
+            _ => return client.send(format!("Usage: {}", self.usage())),
+        };
+
+        let metadata = &target.metadata;
+        let stats = metadata.stats();
+
+        let mut msg = vec![
+            format!("id: {}", target.id),
+            format!("name: {}", target.display_name()),
+            format!(
+                "account: {}",
+                target.account.lock().unwrap().as_deref().unwrap_or("none")
+            ),
+            format!("transport: {}", metadata.transport),
+        ];
+
+        if let Some(handshake) = &metadata.handshake {
+            let mut websocket = format!("websocket: {} ({}", handshake.path, target.protocol());
+
+            if let Some(deflate) = &handshake.deflate {
+                websocket.push_str(&format!(", {deflate}"));
+            }
+
+            msg.push(websocket + ")");
+        }
+
+        // address of other clients is visible only for privileged clients
+        if target.id == client.id || client.has_permission("whois.address") {
+            // clients connected using the Unix domain socket don't have an address
+            match metadata.peer_credentials {
+                Some(credentials) => msg.push(format!("peer: {credentials}")),
+                None => msg.push(format!("address: {}", target.peer_addr()?)),
+            }
+
+            if target.proxied_addr.is_some() {
+                msg.push(format!("proxy: {}", target.socket_addr()?));
+            }
+        }
+
+        let rooms = rooms::client_rooms(target.id);
+
+        msg.extend([
+            format!(
+                "connected: {} ago",
+                format_duration(metadata.connected_for())
+            ),
+            format!("idle: {}", format_duration(metadata.idle_for())),
+            format!(
+                "messages: {} received, {} sent",
+                stats.messages_in, stats.messages_out
+            ),
+            format!(
+                "bytes: {} received, {} sent",
+                stats.bytes_in, stats.bytes_out
+            ),
+            format!("commands: {} ({} errors)", stats.commands, stats.errors),
+            format!(
+                "rooms: {}",
+                if rooms.is_empty() {
+                    "none".to_string()
+                } else {
+                    rooms.join(", ")
+                }
+            ),
+        ]);
+
+        client.send(msg.join("\n"))
+    }
+}
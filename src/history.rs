@@ -0,0 +1,398 @@
+//! History of broadcast and room messages.
+//!
+//! Messages are saved in the history storage selected in the [HISTORY_FILE].
+//! Built-in storages keep the last messages of every room (and of broadcasts)
+//! in memory, the `file` storage also appends them to a JSON lines file
+//! (compacted every `max_messages` messages) and loads the broadcasts again
+//! after the restart (rooms don't survive it).
+//! Messages of rooms are saved by the ID of the room, so a deleted room
+//! created again doesn't show the old messages. Plugins can register other storages
+//! with [Registrar::register_history_storages].
+//!
+//! Example history file:
+//!
+//! ```toml
+//! # Storage of the history (`memory`, `file` or a storage registered by a plugin)
+//! storage = "file"
+//! # Number of messages kept for every room and for broadcasts
+//! max_messages = 100
+//! # File of the `file` storage
+//! file = "history.jsonl"
+//! # Send the last broadcasts to clients after connecting
+//! replay_on_connect = true
+//! # Number of broadcasts sent after connecting
+//! replay_count = 20
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, Once, RwLock,
+    },
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::{
+    moderation::unix_now,
+    plugins::{prelude::*, PluginsManagerType},
+    server::{format_duration, plugins_manager},
+};
+
+/// Path to the history configuration file.
+pub const HISTORY_FILE: &str = "history.toml";
+
+/// Default content of the history configuration file.
+const DEFAULT_HISTORY: &str = r#"# Storage of the history (`memory`, `file` or a storage registered by a plugin)
+storage = "memory"
+# Number of messages kept for every room and for broadcasts
+max_messages = 100
+# File of the `file` storage
+file = "history.jsonl"
+# Send the last broadcasts to clients after connecting
+replay_on_connect = false
+# Number of broadcasts sent after connecting
+replay_count = 20
+"#;
+
+lazy_static! {
+    /// History configuration loaded from the [HISTORY_FILE]
+    pub static ref HISTORY: RwLock<HistoryConfig> =
+        RwLock::new(HistoryConfig::load(HISTORY_FILE).expect("failed to load history configuration"));
+    /// Last messages of the built-in storages by the room IDs (`None` for broadcasts)
+    static ref BUFFERS: Mutex<HashMap<Option<String>, VecDeque<HistoryEntry>>> =
+        Mutex::new(HashMap::new());
+    /// Lock of the file of the `file` storage
+    static ref FILE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Whether the messages of the `file` storage were loaded to the [BUFFERS]
+static FILE_LOADED: Once = Once::new();
+
+/// Number of messages appended to the file of the `file` storage since it was compacted
+static APPENDS: AtomicUsize = AtomicUsize::new(0);
+
+/// History configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Name of the history storage
+    pub storage: String,
+    /// Number of messages kept for every room and for broadcasts
+    pub max_messages: usize,
+    /// File of the `file` storage
+    pub file: PathBuf,
+    /// Send the last broadcasts to clients after connecting
+    pub replay_on_connect: bool,
+    /// Number of broadcasts sent after connecting
+    pub replay_count: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            storage: MemoryStorage.name().to_string(),
+            max_messages: 100,
+            file: PathBuf::from("history.jsonl"),
+            replay_on_connect: false,
+            replay_count: 20,
+        }
+    }
+}
+
+impl HistoryConfig {
+    /// Load the configuration from the file, if the file doesn't exists, create it with default content.
+    pub fn load<P>(path: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            fs::write(path, DEFAULT_HISTORY)?;
+        }
+
+        let content = fs::read_to_string(path)?;
+
+        Ok(toml::from_str(&content)?)
+    }
+}
+
+impl fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ago = Duration::from_secs(unix_now().saturating_sub(self.timestamp));
+
+        write!(f, "[{} ago] {}", format_duration(ago), self.message)
+    }
+}
+
+/// Register default history storages
+pub fn register_history_storages() -> Vec<Box<dyn HistoryStorage>> {
+    vec![Box::new(MemoryStorage), Box::new(FileStorage)]
+}
+
+/// Register default events
+pub fn register_events() -> Vec<Box<dyn Event>> {
+    vec![Box::new(ReplayHistory)]
+}
+
+/// Save the message sent by the client (`None` if sent by the server) to the
+/// room ID (`None` for broadcasts). Errors are logged, so a broken storage
+/// doesn't break sending messages.
+pub async fn record(sender: Option<&Client>, room_id: Option<&str>, message: &str) {
+    let entry = HistoryEntry {
+        timestamp: unix_now(),
+        sender: sender
+            .filter(|client| !client.is_console())
+            .map(|client| client.id),
+        room_id: room_id.map(|room_id| room_id.to_string()),
+        message: message.to_string(),
+    };
+
+    let plugins_manager = plugins_manager();
+
+    let result = match storage(&plugins_manager) {
+        Ok(storage) => storage.push(entry).await,
+        Err(err) => Err(err),
+    };
+
+    if let Err(err) = result {
+        error!("Failed to save message to the history: {}", err);
+    }
+}
+
+/// Returns the last `limit` messages of the room ID (broadcasts if `None`), the oldest first.
+pub async fn recent(room_id: Option<&str>, limit: usize) -> anyhow::Result<Vec<HistoryEntry>> {
+    let plugins_manager = plugins_manager();
+
+    storage(&plugins_manager)?.recent(room_id, limit).await
+}
+
+/// Remove messages of the deleted room from the built-in storages.
+pub fn remove_room(room_id: &str) {
+    BUFFERS.lock().unwrap().remove(&Some(room_id.to_string()));
+}
+
+/// Returns the storage selected in the configuration.
+fn storage(plugins_manager: &PluginsManagerType) -> anyhow::Result<&dyn HistoryStorage> {
+    let name = HISTORY.read().unwrap().storage.clone();
+
+    plugins_manager
+        .history_storages
+        .iter()
+        .find(|storage| storage.name() == name)
+        .map(|storage| storage.as_ref())
+        .ok_or_else(|| anyhow!("unknown history storage `{name}`"))
+}
+
+/// Add the message to the buffer of its room, removing the oldest messages over the limit.
+fn push_buffer(entry: HistoryEntry) {
+    let max_messages = HISTORY.read().unwrap().max_messages;
+
+    let mut buffers = BUFFERS.lock().unwrap();
+    let buffer = buffers.entry(entry.room_id.clone()).or_default();
+
+    buffer.push_back(entry);
+
+    while buffer.len() > max_messages {
+        buffer.pop_front();
+    }
+}
+
+/// Returns the last `limit` messages from the buffer of the room.
+fn recent_buffer(room_id: Option<&str>, limit: usize) -> Vec<HistoryEntry> {
+    let buffers = BUFFERS.lock().unwrap();
+
+    let Some(buffer) = buffers.get(&room_id.map(|room_id| room_id.to_string())) else {
+        return Vec::new();
+    };
+
+    buffer
+        .iter()
+        .skip(buffer.len().saturating_sub(limit))
+        .cloned()
+        .collect()
+}
+
+/// Storage keeping the last messages in memory.
+pub struct MemoryStorage;
+
+#[async_trait]
+impl HistoryStorage for MemoryStorage {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn push(&self, entry: HistoryEntry) -> anyhow::Result<()> {
+        push_buffer(entry);
+
+        Ok(())
+    }
+
+    async fn recent(
+        &self,
+        room_id: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<HistoryEntry>> {
+        Ok(recent_buffer(room_id, limit))
+    }
+}
+
+/// Storage keeping the last messages in memory and appending them to the file.
+pub struct FileStorage;
+
+impl FileStorage {
+    /// Load the broadcasts from the file to the buffers on the first use and
+    /// rewrite the file with only the kept messages.
+    fn load(&self) {
+        FILE_LOADED.call_once(|| {
+            let path = HISTORY.read().unwrap().file.clone();
+
+            if let Err(err) = load_file(&path) {
+                error!("Failed to load history from `{}`: {}", path.display(), err);
+            }
+        });
+    }
+}
+
+fn load_file(path: &Path) -> anyhow::Result<()> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    // rooms (and their IDs) don't survive the restart
+    for entry in content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+        .filter(|entry| entry.room_id.is_none())
+    {
+        push_buffer(entry);
+    }
+
+    let _lock = FILE_LOCK.lock().unwrap();
+
+    let count = compact_file(path)?;
+
+    info!(
+        "Loaded {} messages of the history from `{}`",
+        count,
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Rewrite the file with only the messages kept in the buffers (the caller
+/// must hold the [FILE_LOCK]). Returns the number of the messages.
+fn compact_file(path: &Path) -> anyhow::Result<usize> {
+    let mut entries: Vec<HistoryEntry> = BUFFERS
+        .lock()
+        .unwrap()
+        .values()
+        .flatten()
+        .cloned()
+        .collect();
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    let mut content = String::new();
+    for entry in &entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+
+    // replace the file at once, so the history isn't lost if writing fails
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, path)?;
+
+    APPENDS.store(0, Ordering::SeqCst);
+
+    Ok(entries.len())
+}
+
+#[async_trait]
+impl HistoryStorage for FileStorage {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn push(&self, entry: HistoryEntry) -> anyhow::Result<()> {
+        self.load();
+
+        let path = HISTORY.read().unwrap().file.clone();
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        // keep the same order of the messages in the file and in the buffers
+        let _lock = FILE_LOCK.lock().unwrap();
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?
+            .write_all(line.as_bytes())?;
+
+        push_buffer(entry);
+
+        // messages removed from the buffers are removed from the file from time to time
+        let max_messages = HISTORY.read().unwrap().max_messages;
+
+        if APPENDS.fetch_add(1, Ordering::SeqCst) + 1 >= max_messages.max(1) {
+            compact_file(&path)?;
+        }
+
+        Ok(())
+    }
+
+    async fn recent(
+        &self,
+        room_id: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<HistoryEntry>> {
+        self.load();
+
+        Ok(recent_buffer(room_id, limit))
+    }
+}
+
+/// Send the last broadcasts to the client after connecting (if enabled).
+pub struct ReplayHistory;
+
+#[async_trait]
+impl Event for ReplayHistory {
+    fn event(&self) -> EventType {
+        EventType::OnConnect
+    }
+
+    async fn execute(&self, client: &Client, _data: EventData) -> anyhow::Result<()> {
+        let (replay, count) = {
+            let config = HISTORY.read().unwrap();
+
+            (config.replay_on_connect, config.replay_count)
+        };
+
+        if !replay {
+            return Ok(());
+        }
+
+        for entry in recent(None, count).await? {
+            client.send(entry.to_string())?;
+        }
+
+        Ok(())
+    }
+}
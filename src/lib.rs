@@ -4,8 +4,18 @@ use lazy_static::lazy_static;
 
 use crate::server::Client;
 
+pub mod admin;
+pub mod audit;
+pub mod auth;
 pub mod commands;
+pub mod history;
+pub mod logging;
+pub mod metrics;
+pub mod moderation;
+pub mod permissions;
 pub mod plugins;
+pub mod rooms;
+pub mod routes;
 pub mod server;
 
 lazy_static! {
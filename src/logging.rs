@@ -0,0 +1,329 @@
+//! Logging configuration.
+//!
+//! Logging is configured in the [LOGGING_FILE], the `RUST_LOG` environment
+//! variable and command line options override the filter of the file.
+//!
+//! Example logging file:
+//!
+//! ```toml
+//! # Filter of the logs (the same format as `RUST_LOG`)
+//! level = "info,servers::plugins=debug"
+//! # Format of the logs (`text` or `json`)
+//! format = "json"
+//! # Write logs to the file instead of the standard output
+//! file = "logs/servers.log"
+//! # Start a new file every `minute`, `hour`, `day`, `week` or `never`
+//! rotation = "day"
+//! # Number of rotated files to keep (all files if not set)
+//! max_files = 7
+//! # Logging of the message payloads (`full`, `redacted` or `omitted`)
+//! payloads = "full"
+//! ```
+
+use std::{
+    fmt, fs, io, iter,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::RwLock,
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt::writer::BoxMakeWriter, EnvFilter};
+
+use crate::server::{plugins_manager, Message};
+
+/// Commands handled by the server itself with credentials in their arguments,
+/// the arguments are never logged (registered commands set `has_credentials`).
+const CREDENTIAL_COMMANDS: [&str; 1] = ["/resume"];
+
+/// Prefixes of the messages sent with tokens, the tokens are never logged.
+const CREDENTIAL_REPLIES: [&str; 2] = ["Session token:", "Token:"];
+
+/// Path to the logging configuration file.
+pub const LOGGING_FILE: &str = "logging.toml";
+
+/// Default content of the logging configuration file.
+const DEFAULT_LOGGING: &str = r#"# Filter of the logs (the same format as `RUST_LOG`, e.g. "info,servers::plugins=debug")
+level = "info"
+# Format of the logs (`text` or `json`)
+format = "text"
+# Write logs to the file instead of the standard output
+# file = "logs/servers.log"
+# Start a new file every `minute`, `hour`, `day`, `week` or `never`
+rotation = "never"
+# Number of rotated files to keep (all files if not set)
+# max_files = 7
+# Logging of the message payloads (`full`, `redacted` or `omitted`)
+payloads = "redacted"
+"#;
+
+lazy_static! {
+    /// Logging of the message payloads
+    static ref PAYLOADS: RwLock<PayloadLogging> = RwLock::new(PayloadLogging::default());
+}
+
+/// Logging configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Filter of the logs in the `RUST_LOG` format
+    pub level: String,
+    /// Format of the logs
+    pub format: LogFormat,
+    /// Write logs to the file instead of the standard output
+    pub file: Option<PathBuf>,
+    /// How often a new log file is started
+    pub rotation: LogRotation,
+    /// Number of rotated log files to keep (all files if `None`)
+    pub max_files: Option<usize>,
+    /// Logging of the message payloads
+    pub payloads: PayloadLogging,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::default(),
+            file: None,
+            rotation: LogRotation::default(),
+            max_files: None,
+            payloads: PayloadLogging::default(),
+        }
+    }
+}
+
+/// Format of the logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// JSON object per line
+    Json,
+}
+
+/// How often a new log file is started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    #[default]
+    Never,
+}
+
+/// Logging of the message payloads (messages received from and sent to clients).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadLogging {
+    /// Log the content of the messages
+    Full,
+    /// Log only the length of the messages
+    #[default]
+    Redacted,
+    /// Don't log the messages
+    Omitted,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!(
+                "invalid log format `{s}` (expected `text` or `json`)"
+            )),
+        }
+    }
+}
+
+impl FromStr for LogRotation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "minute" => Ok(Self::Minute),
+            "hour" => Ok(Self::Hour),
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "never" => Ok(Self::Never),
+            _ => Err(anyhow!(
+                "invalid log rotation `{s}` (expected `minute`, `hour`, `day`, `week` or `never`)"
+            )),
+        }
+    }
+}
+
+impl FromStr for PayloadLogging {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "full" => Ok(Self::Full),
+            "redacted" => Ok(Self::Redacted),
+            "omitted" => Ok(Self::Omitted),
+            _ => Err(anyhow!(
+                "invalid payload logging `{s}` (expected `full`, `redacted` or `omitted`)"
+            )),
+        }
+    }
+}
+
+impl From<LogRotation> for Rotation {
+    fn from(rotation: LogRotation) -> Self {
+        match rotation {
+            LogRotation::Minute => Rotation::MINUTELY,
+            LogRotation::Hour => Rotation::HOURLY,
+            LogRotation::Day => Rotation::DAILY,
+            LogRotation::Week => Rotation::WEEKLY,
+            LogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// Load the configuration from the file, if the file doesn't exists, create it with default content.
+    pub fn load<P>(path: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            fs::write(path, DEFAULT_LOGGING)?;
+        }
+
+        let content = fs::read_to_string(path)?;
+
+        Ok(toml::from_str(&content)?)
+    }
+}
+
+/// Initialize the global logger. Logs are written to the standard error
+/// instead of the standard output if `stderr` is `true` and no file is set.
+pub fn init(config: &LoggingConfig, stderr: bool) -> anyhow::Result<()> {
+    let filter = EnvFilter::try_new(&config.level)
+        .map_err(|err| anyhow!("invalid log filter `{}`: {err}", config.level))?;
+
+    let (writer, ansi) = match &config.file {
+        Some(path) => (BoxMakeWriter::new(file_appender(config, path)?), false),
+        None if stderr => (BoxMakeWriter::new(io::stderr), true),
+        None => (BoxMakeWriter::new(io::stdout), true),
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(ansi);
+
+    let result = match config.format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().try_init(),
+    };
+    result.map_err(|err| anyhow!("failed to initialize logging: {err}"))?;
+
+    *PAYLOADS.write().unwrap() = config.payloads;
+
+    Ok(())
+}
+
+/// Create the appender of the log file.
+fn file_appender(config: &LoggingConfig, path: &Path) -> anyhow::Result<RollingFileAppender> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("invalid log file `{}`", path.display()))?;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut builder = RollingFileAppender::builder()
+        .rotation(config.rotation.into())
+        .filename_prefix(file_name);
+
+    if let Some(max_files) = config.max_files {
+        builder = builder.max_log_files(max_files);
+    }
+
+    Ok(builder.build(dir)?)
+}
+
+/// Returns the payload of the message to log, `None` if payloads are omitted.
+/// Credentials in the message are redacted regardless of the configuration.
+pub fn payload(msg: &Message) -> Option<Payload<'_>> {
+    let payloads = *PAYLOADS.read().unwrap();
+
+    if payloads == PayloadLogging::Omitted {
+        return None;
+    }
+
+    if let Some(prefix) = msg.as_text().and_then(credentials_prefix) {
+        return Some(Payload::Credentials(prefix));
+    }
+
+    match payloads {
+        PayloadLogging::Full => Some(Payload::Full(msg)),
+        PayloadLogging::Redacted => Some(Payload::Redacted(msg.len())),
+        PayloadLogging::Omitted => None,
+    }
+}
+
+/// Returns the command or the prefix of the reply if the text contains credentials.
+fn credentials_prefix(text: &str) -> Option<&'static str> {
+    let command = text.split_ascii_whitespace().next()?;
+
+    if let Some(command) = CREDENTIAL_COMMANDS.iter().find(|name| **name == command) {
+        return Some(command);
+    }
+
+    // commands are found by their names and aliases
+    let registered = plugins_manager()
+        .commands
+        .iter()
+        .filter(|cmd| cmd.has_credentials())
+        .find_map(|cmd| {
+            iter::once(cmd.name())
+                .chain(cmd.aliases())
+                .find(|name| *name == command)
+        });
+
+    if registered.is_some() {
+        return registered;
+    }
+
+    CREDENTIAL_REPLIES
+        .iter()
+        .find(|prefix| text.starts_with(*prefix))
+        .copied()
+}
+
+/// Payload of the message formatted for logs.
+#[derive(Debug)]
+pub enum Payload<'a> {
+    Full(&'a Message),
+    /// Length of the redacted message
+    Redacted(usize),
+    /// Command or reply with redacted credentials
+    Credentials(&'static str),
+}
+
+impl fmt::Display for Payload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(msg) => write!(f, "{msg}"),
+            Self::Redacted(len) => write!(f, "<redacted, {len} bytes>"),
+            Self::Credentials(prefix) => write!(f, "{prefix} <redacted>"),
+        }
+    }
+}
@@ -1,5 +1,11 @@
+use std::{env, net::IpAddr, path::PathBuf, time::Duration};
+
 use clap::Parser;
-use servers::server;
+use ipnet::IpNet;
+use servers::{
+    logging::{LogFormat, LogRotation, LoggingConfig, PayloadLogging, LOGGING_FILE},
+    server::{self, Config, DeflateConfig, OverflowPolicy},
+};
 
 #[derive(Debug, Parser)]
 #[clap(
@@ -32,15 +38,245 @@ struct Cli {
         display_order = 3
     )]
     ws_port: u16,
+    #[clap(
+        long = "ws-deflate",
+        help = "Enable permessage-deflate on the WebSocket server, optionally with parameters (e.g. \"server_no_context_takeover; server_max_window_bits=12\")",
+        value_name = "PARAMS",
+        min_values = 0,
+        max_values = 1,
+        default_missing_value = "",
+        display_order = 4
+    )]
+    ws_deflate: Option<DeflateConfig>,
+    #[clap(
+        short = 'p',
+        long = "port",
+        help = "Port serving both TCP and WebSocket clients (disabled by default)",
+        display_order = 5
+    )]
+    port: Option<u16>,
+    #[clap(
+        long = "port-deflate",
+        help = "Enable permessage-deflate for WebSocket clients of the single port, optionally with parameters",
+        value_name = "PARAMS",
+        min_values = 0,
+        max_values = 1,
+        default_missing_value = "",
+        display_order = 6
+    )]
+    port_deflate: Option<DeflateConfig>,
+    #[clap(
+        short = 'u',
+        long = "udp-port",
+        help = "UDP server port (disabled by default)",
+        display_order = 7
+    )]
+    udp_port: Option<u16>,
+    #[clap(
+        long = "udp-session-timeout",
+        help = "End UDP sessions inactive for the given number of seconds",
+        default_value = "60",
+        display_order = 8
+    )]
+    udp_session_timeout: u64,
+    #[clap(
+        long = "udp-max-peers",
+        help = "Max number of UDP sessions, new addresses are ignored over the limit",
+        default_value = "1024",
+        display_order = 9
+    )]
+    udp_max_peers: usize,
+    #[clap(
+        long = "http-port",
+        help = "Port of the HTTP server with /healthz, /readyz and /metrics (disabled by default)",
+        display_order = 10
+    )]
+    http_port: Option<u16>,
+    #[clap(
+        long = "admin-token",
+        help = "Bearer token enabling the admin API on the HTTP server (disabled by default)",
+        env = "SERVERS_ADMIN_TOKEN",
+        hide_env_values = true,
+        display_order = 11
+    )]
+    admin_token: Option<String>,
+    #[clap(
+        long = "console",
+        help = "Read admin commands from stdin (logs are written to stderr)",
+        display_order = 12
+    )]
+    console: bool,
+    #[clap(
+        long = "log-level",
+        help = "Filter of the logs, e.g. \"info,servers::plugins=debug\" (overrides RUST_LOG and logging.toml)",
+        value_name = "FILTER",
+        display_order = 13
+    )]
+    log_level: Option<String>,
+    #[clap(
+        long = "log-format",
+        help = "Format of the logs (text or json)",
+        display_order = 14
+    )]
+    log_format: Option<LogFormat>,
+    #[clap(
+        long = "log-file",
+        help = "Write logs to the file instead of stdout",
+        display_order = 15
+    )]
+    log_file: Option<PathBuf>,
+    #[clap(
+        long = "log-rotation",
+        help = "Start a new log file every minute, hour, day, week or never",
+        display_order = 16
+    )]
+    log_rotation: Option<LogRotation>,
+    #[clap(
+        long = "log-payloads",
+        help = "Logging of the message payloads (full, redacted or omitted)",
+        display_order = 17
+    )]
+    log_payloads: Option<PayloadLogging>,
+    #[clap(
+        long = "unix-socket",
+        help = "Path of the Unix domain socket server",
+        display_order = 18
+    )]
+    unix_socket: Option<PathBuf>,
+    #[clap(
+        long = "unix-socket-mode",
+        help = "Permissions of the Unix domain socket (octal)",
+        default_value = "660",
+        value_parser = parse_mode,
+        display_order = 19
+    )]
+    unix_socket_mode: u32,
+    #[clap(
+        long = "proxy-protocol",
+        help = "Read the PROXY protocol header of connections from trusted proxies",
+        display_order = 20
+    )]
+    proxy_protocol: bool,
+    #[clap(
+        long = "trusted-proxy",
+        help = "Address or network (CIDR) of a trusted proxy, can be used multiple times",
+        value_parser = parse_net,
+        display_order = 21
+    )]
+    trusted_proxies: Vec<IpNet>,
+    #[clap(
+        long = "allowed-origin",
+        help = "Origin allowed to connect to the WebSocket server, can be used multiple times (all origins by default)",
+        display_order = 22
+    )]
+    allowed_origins: Vec<String>,
+    #[clap(
+        long = "require-auth",
+        help = "Require authentication before executing commands",
+        display_order = 23
+    )]
+    require_auth: bool,
+    #[clap(
+        long = "idle-timeout",
+        help = "Disconnect clients idle for the given number of seconds",
+        display_order = 24
+    )]
+    idle_timeout: Option<u64>,
+    #[clap(
+        long = "session-grace",
+        help = "Allow disconnected clients to resume their sessions within the given number of seconds",
+        display_order = 25
+    )]
+    session_grace: Option<u64>,
+    #[clap(
+        long = "send-queue-size",
+        help = "Max number of messages waiting to be sent to a client",
+        default_value = "1024",
+        display_order = 26
+    )]
+    send_queue_size: usize,
+    #[clap(
+        long = "send-queue-policy",
+        help = "What to do when the send queue of a client is full (drop-oldest or disconnect)",
+        default_value = "drop-oldest",
+        display_order = 27
+    )]
+    send_queue_policy: OverflowPolicy,
 }
 
-fn main() {
-    tracing_subscriber::fmt().init();
+fn parse_mode(mode: &str) -> Result<u32, String> {
+    u32::from_str_radix(mode, 8).map_err(|_| format!("invalid octal mode `{mode}`"))
+}
+
+fn parse_net(net: &str) -> Result<IpNet, String> {
+    net.parse::<IpAddr>()
+        .map(IpNet::from)
+        .or_else(|_| net.parse::<IpNet>())
+        .map_err(|_| format!("invalid address or network `{net}`"))
+}
 
+fn main() {
     let args = Cli::parse();
 
+    let mut logging = LoggingConfig::load(LOGGING_FILE).expect("failed to load logging config");
+
+    if let Some(level) = args.log_level.or_else(|| env::var("RUST_LOG").ok()) {
+        logging.level = level;
+    }
+    if let Some(format) = args.log_format {
+        logging.format = format;
+    }
+    if let Some(file) = args.log_file {
+        logging.file = Some(file);
+    }
+    if let Some(rotation) = args.log_rotation {
+        logging.rotation = rotation;
+    }
+    if let Some(payloads) = args.log_payloads {
+        logging.payloads = payloads;
+    }
+
+    // keep the output of the console separated from logs
+    servers::logging::init(&logging, args.console).expect("failed to initialize logging");
+
     let tcp_host = format!("{host}:{port}", host = args.host, port = args.tcp_port);
     let ws_host = format!("{host}:{port}", host = args.host, port = args.ws_port);
 
-    server::run(tcp_host, ws_host).expect("failed to start tcp server");
+    let single_port_host = args
+        .port
+        .map(|port| format!("{host}:{port}", host = args.host));
+
+    let udp_host = args
+        .udp_port
+        .map(|port| format!("{host}:{port}", host = args.host));
+
+    let http_host = args
+        .http_port
+        .map(|port| format!("{host}:{port}", host = args.host));
+
+    let config = Config {
+        tcp_host,
+        ws_host,
+        ws_deflate: args.ws_deflate,
+        single_port_host,
+        single_port_deflate: args.port_deflate,
+        udp_host,
+        udp_session_timeout: Duration::from_secs(args.udp_session_timeout),
+        udp_max_peers: args.udp_max_peers,
+        http_host,
+        admin_token: args.admin_token,
+        console: args.console,
+        unix_socket: args.unix_socket,
+        unix_socket_mode: args.unix_socket_mode,
+        proxy_protocol: args.proxy_protocol,
+        trusted_proxies: args.trusted_proxies,
+        allowed_origins: args.allowed_origins,
+        require_auth: args.require_auth,
+        idle_timeout: args.idle_timeout.map(Duration::from_secs),
+        session_grace: args.session_grace.map(Duration::from_secs),
+        send_queue_size: args.send_queue_size,
+        send_queue_policy: args.send_queue_policy,
+    };
+
+    server::run(config).expect("failed to start tcp server");
 }
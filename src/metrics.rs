@@ -0,0 +1,275 @@
+//! Metrics of the server in the Prometheus text format.
+//!
+//! Metrics are served by the HTTP listener on `/metrics`, see [render].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+
+use crate::{server::Transport, CLIENTS};
+
+/// Upper bounds (in seconds) of the buckets of the command duration histogram.
+pub const DURATION_BUCKETS: [f64; 10] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5, 1.0,
+];
+
+/// All transports, so every metric by transport is exported even if it's zero.
+const TRANSPORTS: [Transport; 4] = [
+    Transport::TCP,
+    Transport::WebSocket,
+    Transport::Unix,
+    Transport::UDP,
+];
+
+lazy_static! {
+    /// Metrics collected since the server started
+    static ref METRICS: Mutex<Metrics> = Mutex::new(Metrics::default());
+}
+
+/// Plugins were loaded and all listeners are bound
+static READY: AtomicBool = AtomicBool::new(false);
+
+/// Metrics collected since the server started
+#[derive(Debug, Default)]
+struct Metrics {
+    /// Accepted connections by transport
+    connections: HashMap<String, u64>,
+    /// Rejected connections by reason
+    rejected: BTreeMap<String, u64>,
+    /// Messages received by transport
+    messages_in: HashMap<String, u64>,
+    /// Messages sent by transport
+    messages_out: HashMap<String, u64>,
+    /// Executed commands by name
+    commands: BTreeMap<String, Histogram>,
+    /// Errors returned by plugin events by event name
+    event_errors: BTreeMap<String, u64>,
+}
+
+/// Histogram of durations.
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Number of observations in each of the [DURATION_BUCKETS]
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+
+        if let Some(i) = DURATION_BUCKETS.iter().position(|bound| secs <= *bound) {
+            self.buckets[i] += 1;
+        }
+
+        self.count += 1;
+        self.sum += secs;
+    }
+}
+
+/// Record a connection accepted by the server.
+pub fn record_connection(transport: Transport) {
+    *METRICS
+        .lock()
+        .unwrap()
+        .connections
+        .entry(transport.to_string())
+        .or_default() += 1;
+}
+
+/// Record a connection rejected by the server (e.g. `banned` or `origin`).
+pub fn record_rejected(reason: &str) {
+    *METRICS
+        .lock()
+        .unwrap()
+        .rejected
+        .entry(reason.to_string())
+        .or_default() += 1;
+}
+
+/// Record a message received from a client.
+pub fn record_message_in(transport: Transport) {
+    *METRICS
+        .lock()
+        .unwrap()
+        .messages_in
+        .entry(transport.to_string())
+        .or_default() += 1;
+}
+
+/// Record a message sent to a client.
+pub fn record_message_out(transport: Transport) {
+    *METRICS
+        .lock()
+        .unwrap()
+        .messages_out
+        .entry(transport.to_string())
+        .or_default() += 1;
+}
+
+/// Record an executed command and how long it took.
+pub fn record_command(name: &str, duration: Duration) {
+    METRICS
+        .lock()
+        .unwrap()
+        .commands
+        .entry(name.to_string())
+        .or_default()
+        .observe(duration);
+}
+
+/// Record an error returned by a plugin event.
+pub fn record_event_error(event: &str) {
+    *METRICS
+        .lock()
+        .unwrap()
+        .event_errors
+        .entry(event.to_string())
+        .or_default() += 1;
+}
+
+/// Mark the server as ready (or not ready) to accept clients.
+pub fn set_ready(ready: bool) {
+    READY.store(ready, Ordering::SeqCst);
+}
+
+/// Returns `true` if plugins were loaded and all listeners are bound.
+pub fn is_ready() -> bool {
+    READY.load(Ordering::SeqCst)
+}
+
+/// Render the metrics in the Prometheus text format.
+pub fn render() -> String {
+    let mut out = String::new();
+
+    // writing to a string can't fail
+    write_metrics(&mut out).unwrap();
+
+    out
+}
+
+fn write_metrics(out: &mut String) -> fmt::Result {
+    let mut connected: HashMap<String, u64> = HashMap::new();
+    for client in CLIENTS.lock().unwrap().values() {
+        *connected
+            .entry(client.metadata.transport.to_string())
+            .or_default() += 1;
+    }
+
+    let metrics = METRICS.lock().unwrap();
+
+    for (name, help, kind, values) in [
+        (
+            "servers_clients_connected",
+            "Number of connected clients.",
+            "gauge",
+            &connected,
+        ),
+        (
+            "servers_connections_total",
+            "Number of accepted connections.",
+            "counter",
+            &metrics.connections,
+        ),
+        (
+            "servers_messages_received_total",
+            "Number of messages received from clients.",
+            "counter",
+            &metrics.messages_in,
+        ),
+        (
+            "servers_messages_sent_total",
+            "Number of messages sent to clients.",
+            "counter",
+            &metrics.messages_out,
+        ),
+    ] {
+        write_header(out, name, help, kind)?;
+
+        for transport in TRANSPORTS {
+            let transport = transport.to_string();
+            let value = values.get(&transport).copied().unwrap_or_default();
+
+            writeln!(out, "{name}{{transport=\"{transport}\"}} {value}")?;
+        }
+    }
+
+    write_header(
+        out,
+        "servers_connections_rejected_total",
+        "Number of rejected connections.",
+        "counter",
+    )?;
+    for (reason, value) in &metrics.rejected {
+        let reason = escape(reason);
+
+        writeln!(
+            out,
+            "servers_connections_rejected_total{{reason=\"{reason}\"}} {value}"
+        )?;
+    }
+
+    let name = "servers_command_duration_seconds";
+    write_header(out, name, "Duration of executed commands.", "histogram")?;
+    for (command, histogram) in &metrics.commands {
+        let command = escape(command);
+
+        let mut cumulative = 0;
+        for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+            cumulative += count;
+
+            writeln!(
+                out,
+                "{name}_bucket{{command=\"{command}\",le=\"{bound}\"}} {cumulative}"
+            )?;
+        }
+
+        let count = histogram.count;
+        writeln!(
+            out,
+            "{name}_bucket{{command=\"{command}\",le=\"+Inf\"}} {count}"
+        )?;
+        writeln!(out, "{name}_sum{{command=\"{command}\"}} {}", histogram.sum)?;
+        writeln!(out, "{name}_count{{command=\"{command}\"}} {count}")?;
+    }
+
+    write_header(
+        out,
+        "servers_event_errors_total",
+        "Number of errors returned by plugin events.",
+        "counter",
+    )?;
+    for (event, value) in &metrics.event_errors {
+        let event = escape(event);
+
+        writeln!(
+            out,
+            "servers_event_errors_total{{event=\"{event}\"}} {value}"
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Write the `HELP` and `TYPE` lines of the metric.
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) -> fmt::Result {
+    writeln!(out, "# HELP {name} {help}")?;
+    writeln!(out, "# TYPE {name} {kind}")
+}
+
+/// Escape the label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,342 @@
+//! Moderation of the clients (bans and mutes).
+//!
+//! Bans are stored in the [BANS_FILE] and are checked when a new connection is
+//! accepted (address bans) and when the client authenticates (account bans).
+
+use std::{
+    fmt, fs,
+    net::IpAddr,
+    path::Path,
+    sync::RwLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use ipnet::IpNet;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::{
+    plugins::prelude::*,
+    server::{end_detached_sessions, end_session, format_duration},
+    CLIENTS,
+};
+
+/// Path to the file with bans.
+pub const BANS_FILE: &str = "bans.toml";
+
+lazy_static! {
+    /// Bans loaded from the [BANS_FILE]
+    pub static ref BANS: RwLock<BanList> =
+        RwLock::new(BanList::load(BANS_FILE).expect("failed to load bans"));
+}
+
+/// Target of the ban.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum BanTarget {
+    /// IP address or network (CIDR)
+    Address(IpNet),
+    /// Account name
+    Account(String),
+}
+
+impl BanTarget {
+    /// Parse the ban target, `account:<name>` for accounts and IP address or
+    /// network (CIDR) for addresses.
+    pub fn parse(target: &str) -> anyhow::Result<Self> {
+        if let Some(account) = target.strip_prefix("account:") {
+            return Ok(Self::Account(account.to_string()));
+        }
+
+        if let Ok(addr) = target.parse::<IpAddr>() {
+            return Ok(Self::Address(addr.into()));
+        }
+
+        match target.parse::<IpNet>() {
+            Ok(net) => Ok(Self::Address(net.trunc())),
+            Err(_) => Err(anyhow!("invalid ban target `{target}`")),
+        }
+    }
+}
+
+impl TryFrom<String> for BanTarget {
+    type Error = anyhow::Error;
+
+    fn try_from(target: String) -> anyhow::Result<Self> {
+        Self::parse(&target)
+    }
+}
+
+impl From<BanTarget> for String {
+    fn from(target: BanTarget) -> Self {
+        target.to_string()
+    }
+}
+
+impl fmt::Display for BanTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(net) => write!(f, "{net}"),
+            Self::Account(account) => write!(f, "account:{account}"),
+        }
+    }
+}
+
+/// Ban entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ban {
+    /// Banned address or account
+    pub target: BanTarget,
+    /// Reason of the ban
+    pub reason: Option<String>,
+    /// Unix timestamp when the ban expires (`None` if the ban is permanent)
+    pub expires_at: Option<u64>,
+    /// Name of the client who created the ban
+    pub banned_by: String,
+}
+
+impl Ban {
+    /// Returns `true` if the ban has expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= unix_now())
+    }
+
+    /// Returns `true` if the ban applies to the address.
+    pub fn matches_addr(&self, addr: IpAddr) -> bool {
+        match &self.target {
+            // IPv4 clients connected to IPv6 sockets use IPv4-mapped addresses
+            BanTarget::Address(net) => net.contains(&addr) || net.contains(&addr.to_canonical()),
+            BanTarget::Account(_) => false,
+        }
+    }
+
+    /// Returns `true` if the ban applies to the account.
+    pub fn matches_account(&self, account: &str) -> bool {
+        matches!(&self.target, BanTarget::Account(name) if name == account)
+    }
+}
+
+impl fmt::Display for Ban {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} by {}", self.target, self.banned_by)?;
+
+        match self.expires_at {
+            Some(expires_at) => write!(
+                f,
+                " (expires in {})",
+                format_duration(Duration::from_secs(expires_at.saturating_sub(unix_now())))
+            )?,
+            None => write!(f, " (permanent)")?,
+        }
+
+        if let Some(reason) = &self.reason {
+            write!(f, ": {reason}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// List of bans persisted in the file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BanList {
+    /// All bans (including expired ones until they are removed).
+    pub bans: Vec<Ban>,
+}
+
+impl BanList {
+    /// Load the bans from the file, if the file doesn't exists, returns an empty list.
+    pub fn load<P>(path: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Save the bans to the file.
+    pub fn save<P>(&self, path: P) -> anyhow::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Add a ban, an existing ban of the same target is replaced.
+    pub fn add(&mut self, ban: Ban) {
+        self.bans.retain(|other| other.target != ban.target);
+        self.bans.push(ban);
+    }
+
+    /// Remove the ban of the target. Returns `false` if the target wasn't banned.
+    pub fn remove(&mut self, target: &BanTarget) -> bool {
+        let len = self.bans.len();
+
+        self.bans.retain(|ban| &ban.target != target);
+
+        self.bans.len() != len
+    }
+
+    /// Remove expired bans.
+    pub fn remove_expired(&mut self) {
+        self.bans.retain(|ban| !ban.is_expired());
+    }
+
+    /// Returns an active ban of the address.
+    pub fn find_addr(&self, addr: IpAddr) -> Option<&Ban> {
+        self.bans
+            .iter()
+            .find(|ban| !ban.is_expired() && ban.matches_addr(addr))
+    }
+
+    /// Returns an active ban of the account.
+    pub fn find_account(&self, account: &str) -> Option<&Ban> {
+        self.bans
+            .iter()
+            .find(|ban| !ban.is_expired() && ban.matches_account(account))
+    }
+}
+
+/// Add the ban and save the ban list.
+pub fn ban(ban: Ban) -> anyhow::Result<()> {
+    let mut bans = BANS.write().unwrap();
+
+    bans.remove_expired();
+    bans.add(ban);
+
+    bans.save(BANS_FILE)
+}
+
+/// Remove the ban and save the ban list. Returns `false` if the target wasn't banned.
+pub fn unban(target: &BanTarget) -> anyhow::Result<bool> {
+    let mut bans = BANS.write().unwrap();
+
+    bans.remove_expired();
+    let removed = bans.remove(target);
+
+    bans.save(BANS_FILE)?;
+
+    Ok(removed)
+}
+
+/// Returns `true` if the address is banned.
+pub fn is_addr_banned(addr: IpAddr) -> bool {
+    BANS.read().unwrap().find_addr(addr).is_some()
+}
+
+/// Returns `true` if the account is banned.
+pub fn is_account_banned(account: &str) -> bool {
+    BANS.read().unwrap().find_account(account).is_some()
+}
+
+/// Parse a duration like `30s`, `10m`, `2h`, `7d` or `1w`.
+pub fn parse_duration(duration: &str) -> anyhow::Result<Duration> {
+    let unit_pos = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow!("missing unit in duration `{duration}`"))?;
+
+    let (value, unit) = duration.split_at(unit_pos);
+
+    let value: u64 = value
+        .parse()
+        .map_err(|_| anyhow!("invalid duration `{duration}`"))?;
+
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(anyhow!("invalid unit in duration `{duration}`")),
+    };
+
+    Ok(Duration::from_secs(value.saturating_mul(secs)))
+}
+
+/// Returns the unix timestamp after the duration from now. Huge durations are
+/// clamped to the max timestamp that can be saved in the bans file.
+pub fn expires_at(duration: Duration) -> u64 {
+    unix_now()
+        .saturating_add(duration.as_secs())
+        .min(i64::MAX as u64)
+}
+
+/// Returns the current unix timestamp in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Disconnect the connected clients the ban applies to and end their detached
+/// sessions. Returns the number of disconnected clients.
+pub fn kick_banned(ban: &Ban) -> anyhow::Result<usize> {
+    let clients: Vec<Client> = CLIENTS.lock().unwrap().values().cloned().collect();
+
+    let reason = format!("banned: {}", ban.reason.as_deref().unwrap_or("no reason"));
+
+    let banned = |target: &Client, addr: Option<IpAddr>| match &ban.target {
+        BanTarget::Address(_) => addr.is_some_and(|addr| ban.matches_addr(addr)),
+        BanTarget::Account(_) => target
+            .account
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|account| ban.matches_account(account)),
+    };
+
+    let ended = end_detached_sessions(banned);
+    if ended > 0 {
+        info!("Ended {} detached sessions of banned clients", ended);
+    }
+
+    let mut kicked = 0;
+    for target in clients {
+        if !banned(&target, target.peer_addr().ok().map(|addr| addr.ip())) {
+            continue;
+        }
+
+        // the ban is already saved, so the other clients are kicked anyway
+        match kick(&target, Some(&reason)) {
+            Ok(()) => kicked += 1,
+            Err(err) => error!("Failed to kick banned client {}: {}", target.id, err),
+        }
+    }
+
+    Ok(kicked)
+}
+
+/// Send the reason to the client and close its connection.
+pub fn kick(client: &Client, reason: Option<&str>) -> anyhow::Result<()> {
+    info!(
+        "Kicking client {}: {}",
+        client.id,
+        reason.unwrap_or("no reason")
+    );
+
+    let sent = match reason {
+        Some(reason) => client.send(format!("You have been kicked: {reason}")),
+        None => client.send("You have been kicked"),
+    };
+
+    // kicked clients can't resume their sessions, even if the reason wasn't sent
+    end_session(client.id);
+
+    client.close()?;
+
+    sent
+}
@@ -0,0 +1,178 @@
+//! Roles and permissions of the clients.
+//!
+//! Permissions are granted to the clients by roles, which are defined in the
+//! policy file ([PERMISSIONS_FILE]). The policy file is created with the
+//! default content if it doesn't exist.
+//!
+//! Example policy file:
+//!
+//! ```toml
+//! # Roles given to every connected client
+//! default_roles = ["user"]
+//!
+//! [roles]
+//! admin = ["*"]
+//! user = ["broadcast"]
+//!
+//! # Roles given to clients connected from the address
+//! [addresses]
+//! "127.0.0.1" = ["admin"]
+//!
+//! # Roles given to clients connected using the Unix domain socket by the user ID
+//! [uids]
+//! "1000" = ["admin"]
+//!
+//! # Permissions required by commands (overrides the permission of the command)
+//! [commands]
+//! "/test" = "test"
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    net::IpAddr,
+    path::Path,
+    sync::RwLock,
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::server::Client;
+
+/// Path to the permissions policy file.
+pub const PERMISSIONS_FILE: &str = "permissions.toml";
+
+/// Default content of the permissions policy file.
+const DEFAULT_POLICY: &str = r#"# Roles given to every connected client
+default_roles = ["user"]
+
+# Permissions of the roles (`*` matches all permissions, `moderation.*` matches
+# all permissions starting with `moderation.`)
+[roles]
+admin = ["*"]
+user = ["broadcast"]
+
+# Roles given to clients connected from the address
+[addresses]
+# "127.0.0.1" = ["admin"]
+
+# Roles given to clients connected using the Unix domain socket by the user ID
+[uids]
+# "1000" = ["admin"]
+
+# Permissions required by commands (overrides the permission of the command)
+[commands]
+# "/test" = "test"
+"#;
+
+lazy_static! {
+    /// Permissions policy loaded from the [PERMISSIONS_FILE]
+    pub static ref POLICY: RwLock<Policy> =
+        RwLock::new(Policy::load(PERMISSIONS_FILE).expect("failed to load permissions policy"));
+}
+
+/// Permissions policy.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// Roles given to every connected client.
+    pub default_roles: Vec<String>,
+    /// Permissions of the roles.
+    pub roles: HashMap<String, Vec<String>>,
+    /// Roles given to clients connected from the address.
+    pub addresses: HashMap<IpAddr, Vec<String>>,
+    /// Roles given to clients connected using the Unix domain socket by the user ID.
+    pub uids: HashMap<String, Vec<String>>,
+    /// Permissions required by commands.
+    pub commands: HashMap<String, String>,
+}
+
+impl Policy {
+    /// Load the policy from the file, if the file doesn't exists, create it with default policy.
+    pub fn load<P>(path: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            fs::write(path, DEFAULT_POLICY)?;
+        }
+
+        let content = fs::read_to_string(path)?;
+
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Returns the roles that should be given to a new client.
+    pub fn initial_roles(&self, addr: Option<IpAddr>, uid: Option<u32>) -> HashSet<String> {
+        let mut roles: HashSet<String> = self.default_roles.iter().cloned().collect();
+
+        if let Some(addr_roles) = addr.and_then(|addr| self.addresses.get(&addr)) {
+            roles.extend(addr_roles.iter().cloned());
+        }
+
+        if let Some(uid_roles) = uid.and_then(|uid| self.uids.get(&uid.to_string())) {
+            roles.extend(uid_roles.iter().cloned());
+        }
+
+        roles
+    }
+
+    /// Returns the permission required to execute the command.
+    pub fn command_permission(
+        &self,
+        command: &str,
+        default: Option<&'static str>,
+    ) -> Option<String> {
+        self.commands
+            .get(command)
+            .cloned()
+            .or_else(|| default.map(|permission| permission.to_string()))
+    }
+
+    /// Returns `true` if the role exists in the policy.
+    pub fn role_exists(&self, role: &str) -> bool {
+        self.roles.contains_key(role)
+    }
+
+    /// Returns `true` if any of the roles grants the permission.
+    pub fn roles_have_permission<'a, I>(&self, roles: I, permission: &str) -> bool
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        roles
+            .into_iter()
+            .filter_map(|role| self.roles.get(role))
+            .flatten()
+            .any(|granted| permission_matches(granted, permission))
+    }
+}
+
+/// Returns `true` if the granted permission matches the required permission.
+fn permission_matches(granted: &str, required: &str) -> bool {
+    if granted == "*" || granted == required {
+        return true;
+    }
+
+    match granted.strip_suffix(".*") {
+        Some(prefix) => required
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('.')),
+        None => false,
+    }
+}
+
+/// Give the client roles from the policy.
+pub fn assign_initial_roles(client: &Client) {
+    let addr = client.peer_addr().ok().map(|addr| addr.ip());
+    let uid = client
+        .metadata
+        .peer_credentials
+        .map(|credentials| credentials.uid);
+
+    let roles = POLICY.read().unwrap().initial_roles(addr, uid);
+
+    client.roles.lock().unwrap().extend(roles);
+}
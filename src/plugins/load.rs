@@ -1,18 +1,47 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
 
 use async_std::task;
+use lazy_static::lazy_static;
 use libloading::{Library, Symbol};
-use tracing::{info, span, trace, Level};
+use tracing::{error, info, span, trace, warn, Level};
 
 use crate::{
-    commands,
+    audit::{self, Actor},
+    auth, commands, history,
     plugins::{
         manager::{PluginsManager, PluginsManagerType},
         prelude::*,
     },
 };
 
+lazy_static! {
+    /// Plugin libraries loaded from the plugins directory, in the order they
+    /// were loaded (libraries are never unloaded, so each file is loaded once)
+    static ref LIBRARIES: Mutex<Vec<LoadedLibrary>> = Mutex::new(Vec::new());
+}
+
+/// Plugin library loaded from the plugins directory.
+struct LoadedLibrary {
+    /// Path to the library file
+    path: PathBuf,
+    /// Modification time of the file when it was loaded
+    modified: Option<SystemTime>,
+    /// Plugins, commands, events, ... registered by the library
+    registered: PluginsManager,
+}
+
 /// Load all plugins, commands and events.
+///
+/// Calling it again rescans the plugins directory: only new files are loaded
+/// and plugins of libraries loaded before are kept (with the same instances).
+/// Changed files aren't loaded again, because loaded libraries can't be
+/// unloaded, the server must be restarted to update them. Files which fail to
+/// load are logged and skipped.
 pub fn loader(plugins_dir: &str) -> anyhow::Result<PluginsManagerType> {
     // if plugins directory doesn't exists, create it
     if !Path::new(plugins_dir).exists() {
@@ -25,40 +54,108 @@ pub fn loader(plugins_dir: &str) -> anyhow::Result<PluginsManagerType> {
     // init a plugins manager
     let mut plugins_manager = PluginsManager::new();
 
-    // register default commands
-    plugins_manager.commands = commands::register_commands();
+    // register default commands, authenticators, events and history storages
+    for command in commands::register_commands() {
+        plugins_manager.register_commands(command);
+    }
+    for authenticator in auth::register_authenticators() {
+        plugins_manager.register_authenticators(authenticator);
+    }
+    for event in history::register_events() {
+        plugins_manager.register_events(event);
+    }
+    for storage in history::register_history_storages() {
+        plugins_manager.register_history_storages(storage);
+    }
+
+    // held during the whole scan, so concurrent rescans don't load a file twice
+    let mut libraries = LIBRARIES.lock().unwrap();
 
     for plugin_path in plugins_files {
-        let path = plugin_path?.path();
-        let path_str = path.to_str().unwrap();
+        let path = match plugin_path {
+            Ok(entry) => entry.path(),
+            Err(err) => {
+                error!("Failed to read the plugins directory: {}", err);
+                continue;
+            },
+        };
+        let path_str = path.to_string_lossy();
 
         // add span to logger
-        let span = span!(Level::TRACE, "", plugin_path = path_str);
+        let span = span!(Level::TRACE, "", plugin_path = %path_str);
         let _enter = span.enter();
 
+        let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+
+        if let Some(library) = libraries.iter().find(|library| library.path == path) {
+            if library.modified != modified {
+                warn!(
+                    "Plugin {} changed since it was loaded, restart the server to load the new version",
+                    path_str
+                );
+            }
+
+            continue;
+        }
+
         info!("Loading plugin {}", path_str);
 
-        // loading library from .so is unsafe
-        unsafe {
-            // Box::new and Box::leak must be there because
-            // if it isn't there it throws an segmentation fault
-            let lib = Box::leak(Box::new(Library::new(&path)?));
-
-            trace!("Finding symbol `plugin_entry` in {}", path_str);
-            let func: Symbol<unsafe extern "C" fn(&mut dyn Registrar) -> ()> =
-                lib.get(b"plugin_entry")?;
-
-            // execute the function `plugin_entry` to load the plugin (possible segmentation fault)
-            trace!("Running function `plugin_entry` from plugin {}", path_str);
-            func(&mut plugins_manager);
+        let registered = match load_library(&path) {
+            Ok(registered) => registered,
+            Err(err) => {
+                error!("Failed to load plugin {}: {}", path_str, err);
+                continue;
+            },
+        };
+
+        for plugin in registered.plugins.iter() {
+            // execute the `on_load` function from the plugin
+            task::block_on(async { plugin.on_load().await });
+            info!("Loaded plugin {}.", plugin.name());
+
+            audit::record(&Actor::server(), "plugin_load", Some(plugin.name()), None);
         }
+
+        libraries.push(LoadedLibrary {
+            path,
+            modified,
+            registered,
+        });
     }
 
-    for plugin in plugins_manager.plugins.iter() {
-        // execute the `on_load` function from the plugin
-        task::block_on(async { plugin.on_load().await });
-        info!("Loaded plugin {}.", plugin.name());
+    for library in libraries.iter() {
+        plugins_manager.extend(&library.registered);
     }
 
     Ok(plugins_manager.into())
 }
+
+/// Load the library and returns what its `plugin_entry` function registered.
+fn load_library(path: &Path) -> anyhow::Result<PluginsManager> {
+    let mut registered = PluginsManager::new();
+
+    unsafe {
+        // loading library from .so is unsafe
+        let lib = Library::new(path)?;
+
+        // a library without the function is unloaded again
+        trace!("Finding symbol `plugin_entry` in {}", path.display());
+        let func: Symbol<unsafe extern "C" fn(&mut dyn Registrar) -> ()> =
+            lib.get(b"plugin_entry")?;
+        let func = *func;
+
+        // the library must stay loaded while the registered plugins are used,
+        // Box::new and Box::leak must be there because
+        // if it isn't there it throws an segmentation fault
+        Box::leak(Box::new(lib));
+
+        // execute the function `plugin_entry` to register the plugin (possible segmentation fault)
+        trace!(
+            "Running function `plugin_entry` from plugin {}",
+            path.display()
+        );
+        func(&mut registered);
+    }
+
+    Ok(registered)
+}
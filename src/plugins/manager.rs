@@ -10,11 +10,15 @@ pub type PluginsManagerType = Arc<PluginsManager>;
 #[derive(Default)]
 pub struct PluginsManager {
     /// Vector with all loaded plugins.
-    pub plugins: Vec<Box<dyn Plugin>>,
+    pub plugins: Vec<Arc<dyn Plugin>>,
     /// Vector with all loaded commands.
-    pub commands: Vec<Box<dyn Command>>,
+    pub commands: Vec<Arc<dyn Command>>,
     /// Vector with all loaded events.
-    pub events: Vec<Box<dyn Event>>,
+    pub events: Vec<Arc<dyn Event>>,
+    /// Vector with all loaded authenticators.
+    pub authenticators: Vec<Arc<dyn Authenticator>>,
+    /// Vector with all loaded history storages.
+    pub history_storages: Vec<Arc<dyn HistoryStorage>>,
 }
 
 impl PluginsManager {
@@ -24,9 +28,23 @@ impl PluginsManager {
             plugins: Vec::new(),
             commands: Vec::new(),
             events: Vec::new(),
+            authenticators: Vec::new(),
+            history_storages: Vec::new(),
         }
     }
 
+    /// Add the plugins, commands, events, authenticators and history storages
+    /// of the other plugins manager, the instances are shared.
+    pub fn extend(&mut self, other: &Self) {
+        self.plugins.extend(other.plugins.iter().cloned());
+        self.commands.extend(other.commands.iter().cloned());
+        self.events.extend(other.events.iter().cloned());
+        self.authenticators
+            .extend(other.authenticators.iter().cloned());
+        self.history_storages
+            .extend(other.history_storages.iter().cloned());
+    }
+
     /// Returns the instance in [PluginsManagerType].
     pub fn into(self) -> PluginsManagerType {
         Arc::new(self)
@@ -39,6 +57,8 @@ impl fmt::Debug for PluginsManager {
             .field("plugins", &self.plugins.len())
             .field("commands", &self.commands.len())
             .field("events", &self.events.len())
+            .field("authenticators", &self.authenticators.len())
+            .field("history_storages", &self.history_storages.len())
             .finish()
     }
 }
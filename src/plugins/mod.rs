@@ -16,5 +16,7 @@ pub mod prelude {
     pub use async_trait::async_trait;
 
     pub use self::types::*;
-    pub use crate::server::{Client, ClientMapValue};
+    pub use crate::server::{
+        find_client, get_client, send_to, Client, ClientMapValue, Extensions, Message,
+    };
 }
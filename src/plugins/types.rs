@@ -1,10 +1,14 @@
 //! Types used for creating plugins.
 
-use std::any::Any;
+use std::{any::Any, fmt, time::Duration};
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 
-use crate::{plugins::manager::PluginsManager, server::Client};
+use crate::{
+    plugins::manager::PluginsManager,
+    server::{Client, Message},
+};
 
 /// A main plugin trait.
 #[async_trait]
@@ -26,6 +30,18 @@ pub trait Command: Any + Send + Sync {
     fn help(&self) -> &'static str;
     /// Usage message of the command.
     fn usage(&self) -> &'static str;
+    /// Permission required to execute the command (`None` if all clients can execute it).
+    fn required_permission(&self) -> Option<&'static str> {
+        None
+    }
+    /// Whether the client must be authenticated to execute the command (when authentication is required).
+    fn requires_auth(&self) -> bool {
+        true
+    }
+    /// Whether the arguments of the command contain credentials (they are never logged).
+    fn has_credentials(&self) -> bool {
+        false
+    }
     /// Command function.
     async fn execute(&self, client: &Client, args: Vec<&str>) -> anyhow::Result<()>;
 }
@@ -39,13 +55,56 @@ pub enum EventType {
     OnSend,
     /// Event executed before command execute (e.g. for disable command).
     OnCommand,
+    /// On client changed nickname.
+    OnNickChange,
+    /// On client kicked another client.
+    OnKick,
+    /// On client banned an address or account.
+    OnBan,
+    /// On client muted another client.
+    OnMute,
+    /// On client sent a binary message (binary messages aren't commands).
+    OnBinaryMessage,
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OnConnect => write!(f, "onConnect"),
+            Self::OnSend => write!(f, "onSend"),
+            Self::OnCommand => write!(f, "onCommand"),
+            Self::OnNickChange => write!(f, "onNickChange"),
+            Self::OnKick => write!(f, "onKick"),
+            Self::OnBan => write!(f, "onBan"),
+            Self::OnMute => write!(f, "onMute"),
+            Self::OnBinaryMessage => write!(f, "onBinaryMessage"),
+        }
+    }
 }
 
 /// All possible to run events.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EventData {
+    /// for `onSend` and `onBinaryMessage` events
+    Message(Message),
     /// for `onCommand` event
     Command(String),
+    /// for `onNickChange` event
+    NickChange {
+        /// Previous nickname of the client.
+        old: Option<String>,
+        /// New nickname of the client.
+        new: String,
+    },
+    /// for `onKick`, `onBan` and `onMute` events
+    Moderation {
+        /// Target of the action (client id, address or account).
+        target: String,
+        /// Reason of the action.
+        reason: Option<String>,
+        /// Duration of the ban or mute (`None` if permanent).
+        duration: Option<Duration>,
+    },
     /// No data
     None,
 }
@@ -59,6 +118,74 @@ pub trait Event: Any + Send + Sync {
     async fn execute(&self, client: &Client, data: EventData) -> anyhow::Result<()>;
 }
 
+/// Credentials sent by the client to authenticate.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Credentials {
+    /// Username and password (e.g. from `/login` command)
+    Password { username: String, password: String },
+    /// Access token (e.g. from WebSocket `Authorization` header)
+    Token(String),
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Password { username, .. } => f
+                .debug_struct("Password")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Self::Token(_) => f.debug_tuple("Token").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Account of the authenticated client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    /// Name of the account.
+    pub name: String,
+    /// Roles given to the client after authentication.
+    pub roles: Vec<String>,
+}
+
+/// Add an authentication backend to the plugin.
+#[async_trait]
+pub trait Authenticator: Any + Send + Sync {
+    /// Name of the authenticator.
+    fn name(&self) -> &'static str;
+    /// Verify the credentials, returns `None` if the credentials are invalid
+    /// (the next authenticator will be tried).
+    async fn authenticate(&self, credentials: &Credentials) -> anyhow::Result<Option<Account>>;
+}
+
+/// Message saved in the history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix timestamp of the message in seconds.
+    pub timestamp: u64,
+    /// ID of the client who sent the message (`None` if sent by the server).
+    pub sender: Option<usize>,
+    /// ID of the room of the message (`None` for broadcasts), see [Room::id](crate::rooms::Room::id).
+    pub room_id: Option<String>,
+    /// Message as it was sent to the clients.
+    pub message: String,
+}
+
+/// Add a storage of the message history to the plugin.
+#[async_trait]
+pub trait HistoryStorage: Any + Send + Sync {
+    /// Name of the storage (selected in the history configuration).
+    fn name(&self) -> &'static str;
+    /// Save the message.
+    async fn push(&self, entry: HistoryEntry) -> anyhow::Result<()>;
+    /// Returns the last `limit` messages of the room ID (broadcasts if `None`), the oldest first.
+    async fn recent(
+        &self,
+        room_id: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<HistoryEntry>>;
+}
+
 /// A plugin registrar trait.
 pub trait Registrar {
     /// Function to register plugins.
@@ -67,18 +194,30 @@ pub trait Registrar {
     fn register_commands(&mut self, command: Box<dyn Command>);
     /// Function to register events.
     fn register_events(&mut self, event: Box<dyn Event>);
+    /// Function to register authenticators.
+    fn register_authenticators(&mut self, authenticator: Box<dyn Authenticator>);
+    /// Function to register history storages.
+    fn register_history_storages(&mut self, storage: Box<dyn HistoryStorage>);
 }
 
 impl Registrar for PluginsManager {
     fn register_plugins(&mut self, plugin: Box<dyn Plugin>) {
-        self.plugins.push(plugin)
+        self.plugins.push(plugin.into())
     }
 
     fn register_commands(&mut self, command: Box<dyn Command>) {
-        self.commands.push(command)
+        self.commands.push(command.into())
     }
 
     fn register_events(&mut self, event: Box<dyn Event>) {
-        self.events.push(event)
+        self.events.push(event.into())
+    }
+
+    fn register_authenticators(&mut self, authenticator: Box<dyn Authenticator>) {
+        self.authenticators.push(authenticator.into())
+    }
+
+    fn register_history_storages(&mut self, storage: Box<dyn HistoryStorage>) {
+        self.history_storages.push(storage.into())
     }
 }
@@ -0,0 +1,247 @@
+//! Named rooms of the clients.
+//!
+//! A room is created when the first client joins it and deleted when the last
+//! client leaves it. The client who created the room is its owner and can set
+//! a password required to join the room.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    sync::Mutex,
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use tracing::{error, info};
+
+use crate::{
+    auth::{generate_token, hash_password, verify_password},
+    history,
+    server::{send_to, Client},
+};
+
+/// Max length of a room name
+pub const MAX_ROOM_NAME_LEN: usize = 32;
+
+lazy_static! {
+    /// List with all rooms
+    pub static ref ROOMS: Mutex<HashMap<String, Room>> = Mutex::new(HashMap::new());
+}
+
+/// Room struct
+#[derive(Debug, Clone)]
+pub struct Room {
+    /// Random ID of the room, a room created again with the same name has a
+    /// new ID (e.g. the history of the deleted room isn't shared with it)
+    pub id: String,
+    /// Name of the room
+    pub name: String,
+    /// ID of the client who owns the room
+    pub owner: Option<usize>,
+    /// Hash of the room password
+    password: Option<String>,
+    /// IDs of the clients in the room
+    pub members: BTreeSet<usize>,
+}
+
+impl Room {
+    /// Returns `true` if a password is required to join the room.
+    pub fn has_password(&self) -> bool {
+        self.password.is_some()
+    }
+}
+
+/// Normalize the room name (remove `#` prefix) and check if it's valid.
+pub fn room_name(name: &str) -> anyhow::Result<String> {
+    let name = name.strip_prefix('#').unwrap_or(name);
+
+    if name.is_empty() || name.len() > MAX_ROOM_NAME_LEN {
+        return Err(anyhow!(
+            "room name must be between 1 and {MAX_ROOM_NAME_LEN} characters long"
+        ));
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(anyhow!(
+            "room name can contain only letters, digits, `_` and `-`"
+        ));
+    }
+
+    Ok(name.to_string())
+}
+
+/// Add the client to the room, if the room doesn't exist it will be created
+/// with the client as the owner.
+pub fn join(client: &Client, name: &str, password: Option<&str>) -> anyhow::Result<()> {
+    let name = room_name(name)?;
+
+    // password hashes are verified and created without holding the lock of rooms,
+    // so the check is repeated if the room changed before the lock was taken again
+    loop {
+        let checked = ROOMS
+            .lock()
+            .unwrap()
+            .get(&name)
+            .map(|room| (room.id.clone(), room.password.clone()));
+
+        // password of the room if the client creates it
+        let new_password = match &checked {
+            Some((_id, Some(hash))) => {
+                if !password.is_some_and(|password| verify_password(hash, password)) {
+                    return Err(anyhow!("invalid password for room `{name}`"));
+                }
+
+                None
+            },
+            Some((_id, None)) => None,
+            None => password.map(hash_password),
+        };
+
+        let mut rooms = ROOMS.lock().unwrap();
+
+        // the room could be created, deleted or its password changed in the meantime
+        let unchanged = match (rooms.get(&name), &checked) {
+            (Some(room), Some((id, hash))) => room.id == *id && room.password == *hash,
+            (None, None) => true,
+            _ => false,
+        };
+
+        if !unchanged {
+            continue;
+        }
+
+        let room = rooms.entry(name.clone()).or_insert_with(|| {
+            info!("Client {} created room `{}`", client.id, name);
+
+            Room {
+                id: generate_token(),
+                name: name.clone(),
+                owner: Some(client.id),
+                password: new_password,
+                members: BTreeSet::new(),
+            }
+        });
+
+        if !room.members.insert(client.id) {
+            return Err(anyhow!("you are already in room `{name}`"));
+        }
+
+        info!("Client {} joined room `{}`", client.id, name);
+
+        return Ok(());
+    }
+}
+
+/// Remove the client from the room. If the owner leaves the room, the ownership is
+/// given to the next member and the room is deleted when the last member leaves it.
+pub fn leave(client_id: usize, name: &str) -> anyhow::Result<()> {
+    let name = room_name(name)?;
+
+    let mut rooms = ROOMS.lock().unwrap();
+
+    let room = rooms
+        .get_mut(&name)
+        .ok_or_else(|| anyhow!("room `{name}` doesn't exist"))?;
+
+    if !room.members.remove(&client_id) {
+        return Err(anyhow!("you are not in room `{name}`"));
+    }
+
+    info!("Client {} left room `{}`", client_id, name);
+
+    if room.owner == Some(client_id) {
+        room.owner = room.members.iter().next().copied();
+    }
+
+    if room.members.is_empty() {
+        if let Some(room) = rooms.remove(&name) {
+            history::remove_room(&room.id);
+        }
+
+        info!("Room `{}` deleted", name);
+    }
+
+    Ok(())
+}
+
+/// Remove the client from all rooms (e.g. when the client disconnects).
+pub fn leave_all(client_id: usize) {
+    for name in client_rooms(client_id) {
+        // the room could be deleted in the meantime
+        let _ = leave(client_id, &name);
+    }
+}
+
+/// Set or remove the password of the room. Only the owner of the room can do it.
+pub fn set_password(client_id: usize, name: &str, password: Option<&str>) -> anyhow::Result<()> {
+    let name = room_name(name)?;
+
+    let password = password.map(hash_password);
+
+    let mut rooms = ROOMS.lock().unwrap();
+
+    let room = rooms
+        .get_mut(&name)
+        .ok_or_else(|| anyhow!("room `{name}` doesn't exist"))?;
+
+    if room.owner != Some(client_id) {
+        return Err(anyhow!("you are not the owner of room `{name}`"));
+    }
+
+    room.password = password;
+
+    Ok(())
+}
+
+/// Returns a room with the name.
+pub fn get_room(name: &str) -> Option<Room> {
+    let name = room_name(name).ok()?;
+
+    ROOMS.lock().unwrap().get(&name).cloned()
+}
+
+/// Returns all rooms sorted by name.
+pub fn rooms() -> Vec<Room> {
+    let mut rooms: Vec<Room> = ROOMS.lock().unwrap().values().cloned().collect();
+
+    rooms.sort_by(|a, b| a.name.cmp(&b.name));
+
+    rooms
+}
+
+/// Returns names of the rooms the client is in.
+pub fn client_rooms(client_id: usize) -> Vec<String> {
+    let mut rooms: Vec<String> = ROOMS
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|room| room.members.contains(&client_id))
+        .map(|room| room.name.clone())
+        .collect();
+
+    rooms.sort();
+
+    rooms
+}
+
+/// Send a message to all clients in the room (queued for detached sessions).
+pub fn send_to_room<S>(name: &str, msg: S) -> anyhow::Result<()>
+where
+    S: ToString,
+    S: fmt::Display,
+{
+    let room = get_room(name).ok_or_else(|| anyhow!("room `{name}` doesn't exist"))?;
+
+    let msg = msg.to_string();
+
+    for id in room.members {
+        if let Err(err) = send_to(id, &msg) {
+            error!("Failed to send message to client {}: {}", id, err);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+//! Routes of the WebSocket server.
+//!
+//! WebSocket clients can connect using different URL paths, each path can put
+//! the clients into a room and limit the commands they can execute. Routes are
+//! defined in the [ROUTES_FILE], paths without a route use the defaults.
+//!
+//! Example routes file:
+//!
+//! ```toml
+//! # Clients connected to `ws://host:port/chat` join the `chat` room and can
+//! # only send messages to it
+//! [routes."/chat"]
+//! room = "chat"
+//! commands = ["/say", "/nick"]
+//! ```
+
+use std::{collections::HashMap, fs, path::Path, sync::RwLock};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::server::Client;
+
+/// Path to the routes file.
+pub const ROUTES_FILE: &str = "routes.toml";
+
+/// Default content of the routes file.
+const DEFAULT_ROUTES: &str = r#"# Routes of the WebSocket server by the URL path of the handshake request
+[routes]
+# [routes."/chat"]
+# # room joined by the clients after connecting
+# room = "chat"
+# # commands available for the clients (all commands if not set)
+# commands = ["/say", "/nick"]
+"#;
+
+lazy_static! {
+    /// Routes loaded from the [ROUTES_FILE]
+    pub static ref ROUTES: RwLock<Routes> =
+        RwLock::new(Routes::load(ROUTES_FILE).expect("failed to load routes"));
+}
+
+/// Routes of the WebSocket server.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Routes {
+    /// Routes by the URL paths.
+    pub routes: HashMap<String, Route>,
+}
+
+/// Route of the WebSocket server.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Route {
+    /// Room joined by the clients after connecting.
+    pub room: Option<String>,
+    /// Commands available for the clients (all commands if `None`).
+    pub commands: Option<Vec<String>>,
+}
+
+impl Routes {
+    /// Load the routes from the file, if the file doesn't exists, create it with default content.
+    pub fn load<P>(path: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            fs::write(path, DEFAULT_ROUTES)?;
+        }
+
+        let content = fs::read_to_string(path)?;
+
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Returns the route of the path.
+    pub fn get(&self, path: &str) -> Option<&Route> {
+        self.routes.get(path)
+    }
+}
+
+impl Route {
+    /// Returns `true` if the command is available on the route.
+    pub fn allows_command(&self, command: &str) -> bool {
+        self.commands
+            .as_ref()
+            .is_none_or(|commands| commands.iter().any(|name| name == command))
+    }
+}
+
+/// Returns the route of the WebSocket path the client connected to.
+pub fn client_route(client: &Client) -> Option<Route> {
+    let path = &client.metadata.handshake.as_ref()?.path;
+
+    ROUTES.read().unwrap().get(path).cloned()
+}
@@ -1,23 +1,51 @@
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt,
-    io::{Read, Write},
+    io::{self, Read, Write},
     net::{Shutdown, SocketAddr, TcpStream},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
-use tracing::info;
-use tungstenite::{accept, Message, WebSocket};
+use anyhow::anyhow;
+use tracing::{info, warn, Span};
+use tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::{HeaderValue, StatusCode},
+    protocol::frame::coding::{CloseCode, Data},
+};
 
-use super::run::PLUGINS_MANAGER;
-use crate::plugins::{
-    prelude::{EventData, EventType},
-    PluginsManagerType,
+#[cfg(unix)]
+use super::peer_credentials;
+use super::{
+    proxy, run, ClientMetadata, DeflateConfig, Enqueued, Extensions, HandshakeRequest, Message,
+    SendQueue, Transport, UdpPeer, WebSocketProtocol, WebSocketStream, CONFIG,
+};
+use crate::{
+    auth, logging, metrics,
+    permissions::POLICY,
+    plugins::{
+        prelude::{Credentials, EventData, EventType},
+        PluginsManagerType,
+    },
+    CLIENTS,
 };
 
+/// Max length of a client nickname
+pub const MAX_NICK_LEN: usize = 24;
+
 /// Max length of a TCP and UDP packet
 pub const MAX_PACKET_LEN: usize = 65536;
 
+/// Duration of a permanent mute (the longest possible mute)
+const MAX_MUTE_DURATION: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
 /// Client struct
 #[derive(Debug, Clone)]
 pub struct Client {
@@ -25,10 +53,30 @@ pub struct Client {
     pub id: usize,
     /// Connection stream of the client
     pub stream: ClientStream,
+    /// Underlying socket, used to shut down the connection without waiting for the stream
+    socket: Socket,
+    /// Messages waiting to be written to the client
+    pub outbound: SendQueue,
     /// Custom Client Map
     pub map: Arc<Mutex<HashMap<String, ClientMapValue>>>,
-    /// Plugins Manager
-    pub plugins_manager: PluginsManagerType,
+    /// Values attached to the client by plugins (stored by their types)
+    pub extensions: Extensions,
+    /// Roles of the client (used for checking permissions)
+    pub roles: Arc<Mutex<HashSet<String>>>,
+    /// Name of the account, if the client is authenticated
+    pub account: Arc<Mutex<Option<String>>>,
+    /// Nickname of the client
+    pub nick: Arc<Mutex<Option<String>>>,
+    /// Credentials sent in the WebSocket handshake request
+    pub(crate) handshake_credentials: Arc<Mutex<Option<Credentials>>>,
+    /// Time until the client is muted
+    pub muted_until: Arc<Mutex<Option<Instant>>>,
+    /// Address of the client reported by a trusted proxy
+    pub(crate) proxied_addr: Option<SocketAddr>,
+    /// Metadata and traffic counters of the connection
+    pub metadata: Arc<ClientMetadata>,
+    /// Span of the connection in the logs
+    pub(crate) span: Span,
 }
 
 // impl Drop for Client {
@@ -40,13 +88,18 @@ pub struct Client {
 // }
 
 /// Value type of the client map entry
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ClientMapValue {
     String(String),
     Array(Vec<String>),
     Bool(bool),
     Int(isize),
     UInt(usize),
+    Float(f64),
+    Bytes(Vec<u8>),
+    List(Vec<ClientMapValue>),
+    Map(HashMap<String, ClientMapValue>),
+    Timestamp(SystemTime),
 }
 
 /// Connection stream of the client
@@ -55,32 +108,203 @@ pub enum ClientStream {
     /// TCP stream
     TCP(Arc<TcpStream>),
     /// WebSocket stream
-    WebSocket(Arc<Mutex<WebSocket<TcpStream>>>),
+    WebSocket(WebSocketStream),
+    /// Unix domain socket stream
+    #[cfg(unix)]
+    Unix(Arc<UnixStream>),
+    /// UDP pseudo-session of a remote address
+    UDP(UdpPeer),
+    /// Standard output of the admin console
+    Console,
+}
+
+/// Underlying socket of the client connection
+#[derive(Debug, Clone)]
+enum Socket {
+    Tcp(Arc<TcpStream>),
+    #[cfg(unix)]
+    Unix(Arc<UnixStream>),
+    Udp(UdpPeer),
+    Console,
+}
+
+impl Socket {
+    fn shutdown(&self) -> io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.shutdown(Shutdown::Both),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.shutdown(Shutdown::Both),
+            Self::Udp(peer) => {
+                peer.close();
+                Ok(())
+            },
+            Self::Console => Ok(()),
+        }
+    }
+}
+
+impl ClientStream {
+    /// Returns the transport of the stream.
+    pub fn transport(&self) -> Transport {
+        match self {
+            Self::TCP(_) => Transport::TCP,
+            Self::WebSocket(_) => Transport::WebSocket,
+            #[cfg(unix)]
+            Self::Unix(_) => Transport::Unix,
+            Self::UDP(_) => Transport::UDP,
+            Self::Console => Transport::Console,
+        }
+    }
+
+    /// Write the message to the stream.
+    fn write(&self, msg: &Message) -> anyhow::Result<()> {
+        match (self, msg) {
+            (Self::TCP(stream), msg) => stream.as_ref().write_all(msg.as_bytes())?,
+            (Self::WebSocket(stream), Message::Text(text)) => stream
+                .writer()
+                .write_message(Data::Text, text.as_bytes().to_vec())?,
+            (Self::WebSocket(stream), Message::Binary(data)) => {
+                stream.writer().write_message(Data::Binary, data.clone())?
+            },
+            #[cfg(unix)]
+            (Self::Unix(stream), msg) => stream.as_ref().write_all(msg.as_bytes())?,
+            (Self::UDP(peer), msg) => peer.send(msg.as_bytes())?,
+            (Self::Console, msg) => {
+                let mut stdout = io::stdout().lock();
+
+                stdout.write_all(msg.as_bytes())?;
+                stdout.write_all(b"\n")?;
+                stdout.flush()?;
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Close the stream.
+    fn close(&self) -> anyhow::Result<()> {
+        match self {
+            Self::TCP(stream) => stream.shutdown(Shutdown::Both)?,
+            Self::WebSocket(stream) => stream.writer().close(Some(CloseCode::Normal))?,
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.shutdown(Shutdown::Both)?,
+            Self::UDP(peer) => peer.close(),
+            Self::Console => {},
+        }
+
+        Ok(())
+    }
 }
 
 impl From<TcpStream> for Client {
     fn from(stream: TcpStream) -> Self {
-        Self {
-            id: 0,
-            stream: ClientStream::TCP(Arc::new(stream)),
-            map: Arc::new(Mutex::new(HashMap::new())),
-            plugins_manager: PLUGINS_MANAGER.clone(),
-        }
+        let stream = Arc::new(stream);
+
+        Self::with_stream(ClientStream::TCP(stream.clone()), Socket::Tcp(stream), None)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixStream> for Client {
+    fn from(stream: UnixStream) -> Self {
+        let stream = Arc::new(stream);
+
+        Self::with_stream(
+            ClientStream::Unix(stream.clone()),
+            Socket::Unix(stream),
+            None,
+        )
     }
 }
 
-impl From<WebSocket<TcpStream>> for Client {
-    fn from(stream: WebSocket<TcpStream>) -> Self {
-        Self {
+impl From<UdpPeer> for Client {
+    fn from(peer: UdpPeer) -> Self {
+        Self::with_stream(ClientStream::UDP(peer.clone()), Socket::Udp(peer), None)
+    }
+}
+
+/// ID of the admin console client (it isn't added to the connected clients)
+pub const CONSOLE_ID: usize = usize::MAX;
+
+impl Client {
+    fn with_stream(
+        stream: ClientStream,
+        socket: Socket,
+        handshake: Option<HandshakeRequest>,
+    ) -> Self {
+        let config = CONFIG.read().unwrap();
+
+        let mut metadata = ClientMetadata::new(stream.transport());
+        metadata.handshake = handshake;
+
+        #[cfg(unix)]
+        if let ClientStream::Unix(stream) = &stream {
+            metadata.peer_credentials = peer_credentials(stream).ok();
+        }
+
+        let client = Self {
             id: 0,
-            stream: ClientStream::WebSocket(Arc::new(Mutex::new(stream))),
+            metadata: Arc::new(metadata),
+            outbound: SendQueue::new(config.send_queue_size, config.send_queue_policy),
+            socket,
+            stream,
             map: Arc::new(Mutex::new(HashMap::new())),
-            plugins_manager: PLUGINS_MANAGER.clone(),
+            extensions: Extensions::default(),
+            roles: Arc::new(Mutex::new(HashSet::new())),
+            account: Arc::new(Mutex::new(None)),
+            nick: Arc::new(Mutex::new(None)),
+            muted_until: Arc::new(Mutex::new(None)),
+            handshake_credentials: Arc::new(Mutex::new(None)),
+            proxied_addr: None,
+            span: Span::none(),
+        };
+
+        let writer = client.clone();
+        thread::spawn(move || writer.write_queued());
+
+        client
+    }
+
+    /// Write queued messages to the stream until the queue is closed.
+    fn write_queued(&self) {
+        while let Some(msg) = self.outbound.pop() {
+            let encoded = match &msg {
+                Message::Text(text) => Message::Text(self.protocol().encode(text.clone())),
+                msg => msg.clone(),
+            };
+
+            if self.stream.write(&encoded).is_err() {
+                self.outbound.fail();
+                break;
+            }
+
+            self.metadata.record_out(msg.len());
+        }
+
+        if self.outbound.is_failed() {
+            // the client was disconnected or didn't read messages fast enough
+            let _ = self.socket.shutdown();
+        } else {
+            let _ = self.stream.close();
         }
     }
-}
 
-impl Client {
+    /// Create the client of the admin console, its messages are written to
+    /// the standard output.
+    pub(crate) fn new_console() -> Self {
+        let mut client = Self::with_stream(ClientStream::Console, Socket::Console, None);
+
+        client.id = CONSOLE_ID;
+        *client.nick.lock().unwrap() = Some("console".to_string());
+
+        client
+    }
+
+    /// Returns `true` if the client is the admin console.
+    pub fn is_console(&self) -> bool {
+        matches!(self.stream, ClientStream::Console)
+    }
+
     /// Create a new TCP Client instance
     pub fn new_tcp(stream: TcpStream, id: usize) -> Self {
         let mut client = Self::from(stream);
@@ -90,105 +314,304 @@ impl Client {
         client
     }
 
-    /// Create a new WebSocket Client instance
+    /// Create a new TCP Client instance for the connection, which address was
+    /// reported by a trusted proxy using the PROXY protocol.
+    pub(crate) fn new_tcp_proxied(
+        stream: TcpStream,
+        id: usize,
+        proxied_addr: Option<SocketAddr>,
+    ) -> Self {
+        let mut client = Self::new_tcp(stream, id);
+
+        client.proxied_addr = proxied_addr;
+
+        client
+    }
+
+    /// Create a new Unix domain socket Client instance
+    #[cfg(unix)]
+    pub fn new_unix(stream: UnixStream, id: usize) -> Self {
+        let mut client = Self::from(stream);
+
+        client.id = id;
+
+        client
+    }
+
+    /// Create a new UDP Client instance for the pseudo-session of the remote address
+    pub fn new_udp(peer: UdpPeer, id: usize) -> Self {
+        let mut client = Self::from(peer);
+
+        client.id = id;
+
+        client
+    }
+
+    /// Create a new WebSocket Client instance, compression is configured by
+    /// the `ws_deflate` option of the server.
     pub fn new_websocket(stream: TcpStream, id: usize) -> anyhow::Result<Self> {
-        let websocket = accept(stream)?;
+        let deflate = CONFIG.read().unwrap().ws_deflate;
+
+        Self::new_websocket_proxied(stream, id, None, deflate)
+    }
+
+    /// Create a new WebSocket Client instance for the connection, which address
+    /// was reported by a trusted proxy using the PROXY protocol. The
+    /// permessage-deflate extension is negotiated if `deflate` is set.
+    pub(crate) fn new_websocket_proxied(
+        stream: TcpStream,
+        id: usize,
+        proxied_addr: Option<SocketAddr>,
+        deflate: Option<DeflateConfig>,
+    ) -> anyhow::Result<Self> {
+        let handshake = Arc::new(Mutex::new(None));
+
+        let rejected_origin = Arc::new(AtomicBool::new(false));
+
+        let captured_handshake = handshake.clone();
+        let rejected = rejected_origin.clone();
+        // the error type of the callback is defined by tungstenite
+        #[allow(clippy::result_large_err)]
+        let callback = move |req: &Request, mut res: Response| {
+            let request = handshake_request(req);
+
+            if let Some(origin) = request.header("Origin") {
+                if !CONFIG.read().unwrap().is_origin_allowed(origin) {
+                    info!("Rejected WebSocket connection from origin `{}`", origin);
+                    metrics::record_rejected("origin");
+                    rejected.store(true, Ordering::SeqCst);
+
+                    let res = ErrorResponse::new(Some("origin not allowed".to_string()));
+                    return Err(forbidden(res));
+                }
+            }
+
+            if let Some(protocol) = request.protocol {
+                res.headers_mut().insert(
+                    "Sec-WebSocket-Protocol",
+                    HeaderValue::from_static(protocol.as_str()),
+                );
+            }
+
+            *captured_handshake.lock().unwrap() = Some(request);
+
+            Ok(res)
+        };
+
+        let socket = Socket::Tcp(Arc::new(stream.try_clone()?));
+
+        let websocket = WebSocketStream::accept(stream, callback, deflate).inspect_err(|_| {
+            // rejected origins are already recorded
+            if !rejected_origin.load(Ordering::SeqCst) {
+                metrics::record_rejected("handshake");
+            }
+        })?;
+
+        let mut handshake = handshake.lock().unwrap().take();
+        if let Some(handshake) = &mut handshake {
+            handshake.deflate = websocket.deflate().copied();
+        }
+
+        // capture credentials from the `Authorization` header or the `token` query parameter
+        let credentials = handshake.as_ref().and_then(|handshake| {
+            handshake
+                .header("Authorization")
+                .and_then(auth::parse_authorization)
+                .or_else(|| {
+                    handshake
+                        .query_param("token")
+                        .map(|token| Credentials::Token(token.to_string()))
+                })
+        });
+
+        if let Some(handshake) = &mut handshake {
+            handshake.remove_credentials();
+        }
+
+        let forwarded_for = handshake
+            .as_ref()
+            .and_then(|handshake| handshake.header("X-Forwarded-For"))
+            .map(|header| header.to_string());
 
-        let mut client = Self::from(websocket);
+        let mut client = Self::with_stream(ClientStream::WebSocket(websocket), socket, handshake);
 
         client.id = id;
+        client.handshake_credentials = Arc::new(Mutex::new(credentials));
+        client.proxied_addr = proxied_addr;
+
+        // honour `X-Forwarded-For` only from trusted proxies
+        if let Some(header) = forwarded_for {
+            let peer = client.peer_addr()?;
+            let config = CONFIG.read().unwrap();
+
+            if let Some(ip) =
+                proxy::forwarded_for(&header, peer.ip(), |ip| config.is_trusted_proxy(ip))
+            {
+                // the header doesn't contain the port of the client
+                client.proxied_addr = Some(SocketAddr::new(ip, 0));
+            }
+        }
 
         Ok(client)
     }
 
-    /// Recieve a message from the client
+    /// Returns the subprotocol of the connection (plain text if the client is
+    /// not connected using WebSocket or didn't request any subprotocol).
+    pub fn protocol(&self) -> WebSocketProtocol {
+        self.metadata
+            .handshake
+            .as_ref()
+            .and_then(|handshake| handshake.protocol)
+            .unwrap_or(WebSocketProtocol::Text)
+    }
+
+    /// Recieve a text message from the client, returns an error if the client sent a binary message
     pub fn read(&self) -> anyhow::Result<String> {
-        // read the message from the stream
-        let mut msg = match &self.stream {
-            ClientStream::TCP(stream) => {
-                // allocate an empty buffer
-                let mut buf = [0; MAX_PACKET_LEN];
+        match self.read_message()? {
+            Message::Text(text) => Ok(text),
+            Message::Binary(_) => Err(anyhow!("received binary message")),
+        }
+    }
 
-                // read the message and get length of it
-                let len = stream.as_ref().read(&mut buf)?;
+    /// Recieve a text or binary message from the client
+    pub fn read_message(&self) -> anyhow::Result<Message> {
+        // read the message from the stream
+        let msg = match &self.stream {
+            ClientStream::TCP(stream) => read_packet(stream.as_ref())?,
+            #[cfg(unix)]
+            ClientStream::Unix(stream) => read_packet(stream.as_ref())?,
+            ClientStream::UDP(peer) => {
+                let timeout = CONFIG.read().unwrap().udp_session_timeout;
+
+                Message::from_bytes(peer.recv(timeout)?)
+            },
+            ClientStream::Console => {
+                return Err(anyhow!("console client doesn't receive messages"))
+            },
+            ClientStream::WebSocket(stream) => loop {
+                match stream.read_message()? {
+                    (Data::Text, data) => {
+                        match self.protocol().decode(String::from_utf8(data)?) {
+                            Ok(text) => break Message::Text(text),
+                            // tell the client and wait for the next message
+                            Err(err) => self.send(err)?,
+                        }
+                    },
+                    (_opcode, data) => break Message::Binary(data),
+                }
+            },
+        };
 
-                // select only used bytes in the buffer
-                let buf = &buf[0..len];
+        self.metadata.record_in(msg.len());
 
-                // decode buffer (&[u8]) to a String
-                String::from_utf8(buf.to_vec())?
-            },
-            ClientStream::WebSocket(stream) => {
-                // read the message from the stream
-                let msg = stream.lock().unwrap().read_message()?;
+        let msg = match msg {
+            Message::Text(mut text) => {
+                // remove new line characters
+                while text.ends_with('\n') || text.ends_with('\r') {
+                    text.pop();
+                }
 
-                // decode message to a String
-                msg.to_string()
+                Message::Text(text)
             },
+            msg => msg,
         };
 
-        // remove new line characters
-        while msg.ends_with('\n') || msg.ends_with('\r') {
-            msg.pop();
+        if let Some(payload) = logging::payload(&msg) {
+            info!("[Recieved]: {}", payload);
         }
 
-        info!("[Recieved]: {}", msg);
-
         Ok(msg)
     }
 
-    /// Send a message to the client
+    /// Send a text message to the client. The message is queued and written by the
+    /// writer thread of the client, so it doesn't block.
     pub fn send<S>(&self, msg: S) -> anyhow::Result<()>
     where
         S: ToString,
         S: fmt::Display,
     {
-        // convert the message into a string
-        let msg = msg.to_string();
+        self.send_message(Message::Text(msg.to_string()))
+    }
 
-        // convert the message into bytes to send it
-        let buf = msg.as_bytes();
+    /// Send a text or binary message to the client without blocking.
+    pub fn send_message<M>(&self, msg: M) -> anyhow::Result<()>
+    where
+        M: Into<Message>,
+    {
+        let msg = msg.into();
+
+        match self.outbound.push(msg.clone()) {
+            Ok(Enqueued::Queued) => {},
+            Ok(Enqueued::DroppedOldest) => {
+                warn!(
+                    "Send queue of client {} is full, dropped the oldest message",
+                    self.id
+                )
+            },
+            Err(err) => {
+                if self.outbound.is_failed() {
+                    // unblock the writer and the reader of the slow client
+                    let _ = self.socket.shutdown();
+                }
 
-        // send the message
-        match &self.stream {
-            ClientStream::TCP(stream) => stream.as_ref().write_all(buf)?,
-            ClientStream::WebSocket(stream) => {
-                stream.lock().unwrap().write_message(Message::from(buf))?
+                return Err(err);
             },
         }
 
-        info!("[Sent]: {}", msg);
+        if let Some(payload) = logging::payload(&msg) {
+            info!("[Sent]: {}", payload);
+        }
 
         Ok(())
     }
 
-    /// Returns the socket address of the remote peer of this connection.
+    /// Returns the socket address of the client, behind a trusted proxy it's the
+    /// address reported by the proxy. Clients connected using a Unix domain socket
+    /// don't have an address.
     pub fn peer_addr(&self) -> anyhow::Result<SocketAddr> {
-        let addr = match &self.stream {
-            ClientStream::TCP(stream) => stream.peer_addr()?,
-            ClientStream::WebSocket(stream) => stream.lock().unwrap().get_ref().peer_addr()?,
-        };
+        match self.proxied_addr {
+            Some(addr) => Ok(addr),
+            None => self.socket_addr(),
+        }
+    }
 
-        Ok(addr)
+    /// Returns the socket address of the remote peer of this connection (the
+    /// address of the proxy if the client is behind one).
+    pub fn socket_addr(&self) -> anyhow::Result<SocketAddr> {
+        match &self.socket {
+            Socket::Tcp(stream) => Ok(stream.peer_addr()?),
+            #[cfg(unix)]
+            Socket::Unix(_) => Err(anyhow!("client is connected using a unix socket")),
+            Socket::Udp(peer) => Ok(peer.peer_addr()),
+            Socket::Console => Err(anyhow!("client is the admin console")),
+        }
     }
 
-    /// Flush this output stream, ensuring that all intermediately buffered contents reach their destination.
+    /// Flush this output stream, waiting until all queued messages reach their destination.
     pub fn flush(&self) -> anyhow::Result<()> {
+        self.outbound.wait_idle();
+
         match &self.stream {
             ClientStream::TCP(stream) => stream.as_ref().flush()?,
             ClientStream::WebSocket(_stream) => {},
+            #[cfg(unix)]
+            ClientStream::Unix(stream) => stream.as_ref().flush()?,
+            ClientStream::UDP(_peer) => {},
+            ClientStream::Console => {},
         }
 
         Ok(())
     }
 
-    /// Close the client connection
+    /// Close the client connection after the queued messages are written. The
+    /// admin console can't be closed, it would stop writing its output.
     pub fn close(&self) -> anyhow::Result<()> {
-        match &self.stream {
-            ClientStream::TCP(stream) => stream.shutdown(Shutdown::Both)?,
-            ClientStream::WebSocket(stream) => stream.lock().unwrap().close(None)?,
+        if self.is_console() {
+            return Ok(());
         }
 
+        self.outbound.close();
+
         Ok(())
     }
 
@@ -208,6 +631,11 @@ impl Client {
         self.map.lock().unwrap().get(&key.to_string()).cloned()
     }
 
+    /// Returns values attached to the client by plugins.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
     /// Delete key from the map.
     pub fn delete_key<S>(&self, key: S) -> Option<ClientMapValue>
     where
@@ -216,17 +644,254 @@ impl Client {
         self.map.lock().unwrap().remove(&key.to_string())
     }
 
+    /// Returns `true` if the client has the permission.
+    pub fn has_permission(&self, permission: &str) -> bool {
+        // the admin console is privileged
+        if self.is_console() {
+            return true;
+        }
+
+        let roles = self.roles.lock().unwrap();
+
+        POLICY
+            .read()
+            .unwrap()
+            .roles_have_permission(roles.iter(), permission)
+    }
+
+    /// Returns the plugins manager (the current one, the plugins directory can be rescanned).
+    pub fn plugins_manager(&self) -> PluginsManagerType {
+        run::plugins_manager()
+    }
+
+    /// Returns the nickname of the client or its id if the nickname isn't set.
+    pub fn display_name(&self) -> String {
+        match &*self.nick.lock().unwrap() {
+            Some(nick) => nick.clone(),
+            None => format!("#{}", self.id),
+        }
+    }
+
+    /// Change the nickname of the client and run `onNickChange` events.
+    ///
+    /// Returns an error if the nickname is invalid or already used by another client.
+    pub async fn set_nick<S>(&self, nick: S) -> anyhow::Result<()>
+    where
+        S: ToString,
+    {
+        let nick = nick.to_string();
+
+        validate_nick(&nick)?;
+
+        let old = {
+            // hold the lock of clients to make sure nobody takes the nickname in the meantime
+            let clients = CLIENTS.lock().unwrap();
+
+            let taken = clients.values().any(|client| {
+                client.id != self.id
+                    && client
+                        .nick
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .is_some_and(|other| other.eq_ignore_ascii_case(&nick))
+            });
+
+            if taken {
+                return Err(anyhow!("nickname `{nick}` is already taken"));
+            }
+
+            self.nick.lock().unwrap().replace(nick.clone())
+        };
+
+        self.span.record("nick", nick.as_str());
+
+        info!(
+            "Client {} changed nickname from `{}` to `{}`",
+            self.id,
+            old.as_deref().unwrap_or_default(),
+            nick
+        );
+
+        self.run_events(
+            EventType::OnNickChange,
+            EventData::NickChange { old, new: nick },
+        )
+        .await
+    }
+
+    /// Mute the client for the duration (`None` mutes the client until unmuted).
+    pub fn mute(&self, duration: Option<Duration>) {
+        // a permanent mute is a mute long enough to outlive the connection,
+        // longer mutes are shortened to it, so the time of the end can't overflow
+        let duration = duration.map_or(MAX_MUTE_DURATION, |duration| {
+            duration.min(MAX_MUTE_DURATION)
+        });
+
+        *self.muted_until.lock().unwrap() = Instant::now().checked_add(duration);
+    }
+
+    /// Unmute the client. Returns `false` if the client wasn't muted.
+    pub fn unmute(&self) -> bool {
+        let was_muted = self.is_muted();
+
+        *self.muted_until.lock().unwrap() = None;
+
+        was_muted
+    }
+
+    /// Returns `true` if the client is muted and can't send messages to other clients.
+    pub fn is_muted(&self) -> bool {
+        self.muted_until
+            .lock()
+            .unwrap()
+            .is_some_and(|until| until > Instant::now())
+    }
+
+    /// Returns `true` if the client is authenticated.
+    pub fn is_authenticated(&self) -> bool {
+        self.account.lock().unwrap().is_some()
+    }
+
+    /// Adds a role to the client. Returns `false` if the client already had the role.
+    pub fn grant_role<S>(&self, role: S) -> bool
+    where
+        S: ToString,
+    {
+        self.roles.lock().unwrap().insert(role.to_string())
+    }
+
+    /// Removes a role from the client. Returns `false` if the client didn't have the role.
+    pub fn revoke_role<S>(&self, role: S) -> bool
+    where
+        S: ToString,
+    {
+        self.roles.lock().unwrap().remove(&role.to_string())
+    }
+
     pub async fn run_events(
         &self,
         event_type: EventType,
         event_data: EventData,
     ) -> anyhow::Result<()> {
-        for event in self.plugins_manager.events.iter() {
+        for event in self.plugins_manager().events.iter() {
             if event.event() == event_type {
-                event.execute(self, event_data.clone()).await?;
+                if let Err(err) = event.execute(self, event_data.clone()).await {
+                    metrics::record_event_error(&event_type.to_string());
+                    return Err(err);
+                }
             }
         }
 
         Ok(())
     }
 }
+
+/// Read a packet from the stream, payloads which aren't valid UTF-8 are binary messages.
+///
+/// A character split between reads of the stream is completed by the next read,
+/// so a text message isn't received as two binary messages.
+fn read_packet<R>(mut stream: R) -> anyhow::Result<Message>
+where
+    R: Read,
+{
+    // allocate an empty buffer
+    let mut buf = [0; MAX_PACKET_LEN];
+    let mut data = Vec::new();
+
+    loop {
+        // read the message and get length of it
+        let len = stream.read(&mut buf)?;
+
+        // the connection was closed by the client
+        if len == 0 {
+            return Err(anyhow!("disconnected"));
+        }
+
+        data.extend_from_slice(&buf[0..len]);
+
+        match std::str::from_utf8(&data) {
+            // the data ends with an incomplete character
+            Err(err) if err.error_len().is_none() && data.len() < MAX_PACKET_LEN => continue,
+            _ => break,
+        }
+    }
+
+    Ok(Message::from_bytes(data))
+}
+
+/// Check if the nickname is valid.
+///
+/// A valid nickname starts with a letter (so it can't be confused with a client id)
+/// and contains only ASCII letters, digits, `_` and `-`.
+pub fn validate_nick(nick: &str) -> anyhow::Result<()> {
+    if nick.is_empty() || nick.len() > MAX_NICK_LEN {
+        return Err(anyhow!(
+            "nickname must be between 1 and {MAX_NICK_LEN} characters long"
+        ));
+    }
+
+    if !nick.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(anyhow!("nickname must start with a letter"));
+    }
+
+    if !nick
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(anyhow!(
+            "nickname can contain only letters, digits, `_` and `-`"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Capture the path, headers and cookies of the WebSocket handshake request and
+/// negotiate the subprotocol.
+fn handshake_request(req: &Request) -> HandshakeRequest {
+    let mut headers: HashMap<String, String> = HashMap::new();
+
+    for (name, value) in req.headers() {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+
+        headers
+            .entry(name.as_str().to_string())
+            .and_modify(|values| {
+                values.push_str(", ");
+                values.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+
+    let cookies = req
+        .headers()
+        .get_all("Cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+    let protocol = headers
+        .get("sec-websocket-protocol")
+        .and_then(|requested| WebSocketProtocol::negotiate(requested));
+
+    HandshakeRequest {
+        path: req.uri().path().to_string(),
+        query: req.uri().query().map(|query| query.to_string()),
+        headers,
+        cookies,
+        protocol,
+        deflate: None,
+    }
+}
+
+/// Set the status of the handshake error response to `403 Forbidden`.
+fn forbidden(mut res: ErrorResponse) -> ErrorResponse {
+    *res.status_mut() = StatusCode::FORBIDDEN;
+    res
+}
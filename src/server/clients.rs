@@ -0,0 +1,67 @@
+use std::fmt;
+
+use anyhow::anyhow;
+use tracing::error;
+
+use super::{queue_broadcast, queue_message, Client};
+use crate::CLIENTS;
+
+/// Returns a connected client with the id.
+pub fn get_client(id: usize) -> Option<Client> {
+    CLIENTS.lock().unwrap().get(&id).cloned()
+}
+
+/// Find a connected client by its id or nickname (case-insensitive).
+pub fn find_client(target: &str) -> Option<Client> {
+    if let Ok(id) = target.parse() {
+        return get_client(id);
+    }
+
+    CLIENTS
+        .lock()
+        .unwrap()
+        .values()
+        .find(|client| {
+            client
+                .nick
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|nick| nick.eq_ignore_ascii_case(target))
+        })
+        .cloned()
+}
+
+/// Send a message to the connected client with the id. If the client has a detached
+/// session, the message is queued until the session is resumed.
+pub fn send_to<S>(id: usize, msg: S) -> anyhow::Result<()>
+where
+    S: ToString,
+    S: fmt::Display,
+{
+    match get_client(id) {
+        Some(client) => client.send(msg),
+        None if queue_message(id, msg.to_string()) => Ok(()),
+        None => Err(anyhow!("client {id} is not connected")),
+    }
+}
+
+/// Send a message to all connected clients and queue it for detached sessions.
+/// Returns the number of connected clients the message was sent to.
+pub fn broadcast(msg: &str) -> usize {
+    let clients: Vec<Client> = CLIENTS.lock().unwrap().values().cloned().collect();
+
+    for client in &clients {
+        if let Err(err) = client.send(msg) {
+            error!(
+                "Failed to send broadcast message to client {}: {}",
+                client.id, err
+            );
+        }
+    }
+
+    // deliver the message also to clients that will resume their sessions
+    queue_broadcast(msg);
+
+    clients.len()
+}
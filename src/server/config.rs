@@ -0,0 +1,106 @@
+use std::{net::IpAddr, path::PathBuf, sync::RwLock, time::Duration};
+
+use ipnet::IpNet;
+use lazy_static::lazy_static;
+
+use super::{DeflateConfig, OverflowPolicy};
+
+lazy_static! {
+    /// Configuration of the running server
+    pub static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
+}
+
+/// Server configuration
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Address of the TCP server
+    pub tcp_host: String,
+    /// Address of the WebSocket server
+    pub ws_host: String,
+    /// permessage-deflate compression of the WebSocket server (disabled if `None`)
+    pub ws_deflate: Option<DeflateConfig>,
+    /// Address of the server accepting both TCP and WebSocket clients, the protocol
+    /// is detected from the first bytes of the connection (disabled if `None`)
+    pub single_port_host: Option<String>,
+    /// permessage-deflate compression of WebSocket clients of the single-port
+    /// server (disabled if `None`)
+    pub single_port_deflate: Option<DeflateConfig>,
+    /// Address of the UDP server (disabled if `None`)
+    pub udp_host: Option<String>,
+    /// End UDP pseudo-sessions that haven't sent any datagram for this time
+    pub udp_session_timeout: Duration,
+    /// Max number of UDP pseudo-sessions, datagrams starting new sessions are
+    /// ignored over the limit
+    pub udp_max_peers: usize,
+    /// Address of the HTTP server with health checks and metrics (disabled if `None`)
+    pub http_host: Option<String>,
+    /// Bearer token of the admin API of the HTTP server (disabled if `None`)
+    pub admin_token: Option<String>,
+    /// Read admin commands from the standard input
+    pub console: bool,
+    /// Path of the Unix domain socket server (disabled if `None`)
+    pub unix_socket: Option<PathBuf>,
+    /// Permissions of the Unix domain socket file
+    pub unix_socket_mode: u32,
+    /// Read the PROXY protocol header of TCP and WebSocket connections from trusted proxies
+    pub proxy_protocol: bool,
+    /// Networks of the proxies trusted to report addresses of the clients (PROXY
+    /// protocol and `X-Forwarded-For` header)
+    pub trusted_proxies: Vec<IpNet>,
+    /// Origins allowed to connect to the WebSocket server (all origins if empty)
+    pub allowed_origins: Vec<String>,
+    /// Allow only commands that don't require authentication until the client is authenticated
+    pub require_auth: bool,
+    /// Disconnect clients that haven't sent any message for this time
+    pub idle_timeout: Option<Duration>,
+    /// Keep the state of disconnected clients for this time so they can resume their sessions
+    pub session_grace: Option<Duration>,
+    /// Max number of messages waiting to be sent to a client
+    pub send_queue_size: usize,
+    /// What to do when the send queue of a client is full
+    pub send_queue_policy: OverflowPolicy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tcp_host: "0.0.0.0:9999".to_string(),
+            ws_host: "0.0.0.0:9998".to_string(),
+            ws_deflate: None,
+            single_port_host: None,
+            single_port_deflate: None,
+            udp_host: None,
+            udp_session_timeout: Duration::from_secs(60),
+            udp_max_peers: 1024,
+            http_host: None,
+            admin_token: None,
+            console: false,
+            unix_socket: None,
+            unix_socket_mode: 0o660,
+            proxy_protocol: false,
+            trusted_proxies: Vec::new(),
+            allowed_origins: Vec::new(),
+            require_auth: false,
+            idle_timeout: None,
+            session_grace: None,
+            send_queue_size: 1024,
+            send_queue_policy: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+impl Config {
+    /// Returns `true` if the address belongs to a trusted proxy.
+    pub fn is_trusted_proxy(&self, addr: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&addr))
+    }
+
+    /// Returns `true` if WebSocket clients from the origin can connect.
+    pub fn is_origin_allowed(&self, origin: &str) -> bool {
+        self.allowed_origins.is_empty()
+            || self
+                .allowed_origins
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+    }
+}
@@ -0,0 +1,201 @@
+//! Admin console reading commands from the standard input of the server.
+//!
+//! Lines starting with `/` execute registered commands as a privileged client
+//! (without permission checks and `onCommand` events), other lines are console
+//! commands, see [CONSOLE_COMMANDS]. Output of the console is written to the
+//! standard output, logs are written to the standard error when the console
+//! is enabled.
+
+use std::{
+    io::{self, BufRead},
+    time::Instant,
+};
+
+use anyhow::anyhow;
+use async_std::task;
+use tracing::{error, info};
+
+use super::{find_client, format_duration, plugins_manager, shutdown, Client};
+use crate::{
+    audit::{self, Actor},
+    metrics, moderation, rooms, CLIENTS,
+};
+
+/// Commands available only in the console with their usage and help.
+pub const CONSOLE_COMMANDS: [(&str, &str); 5] = [
+    ("help", "Show console commands"),
+    ("clients", "Show list of connected clients"),
+    ("kick <client id|nick> [reason]", "Disconnect the client"),
+    ("plugins", "Show loaded plugins, commands and events"),
+    ("shutdown", "Disconnect all clients and stop the server"),
+];
+
+/// Read commands from the standard input until it's closed.
+pub(crate) fn start_console() {
+    let client = Client::new_console();
+
+    info!("Admin console started");
+
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                error!("Failed to read the console input: {}", err);
+                break;
+            },
+        };
+
+        if let Err(err) = task::block_on(execute(&client, line.trim())) {
+            // the standard output could be broken, so the error goes to the standard error
+            if let Err(send_err) = client.send(format!("error: {err}")) {
+                eprintln!("error: {err} (failed to write the console output: {send_err})");
+            }
+        }
+    }
+
+    info!("Admin console input closed");
+}
+
+/// Execute the console line.
+async fn execute(client: &Client, line: &str) -> anyhow::Result<()> {
+    let args: Vec<&str> = line.split_ascii_whitespace().collect();
+
+    let Some((&name, args)) = args.split_first() else {
+        return Ok(());
+    };
+
+    match name {
+        "help" => {
+            let mut msg: Vec<String> = CONSOLE_COMMANDS
+                .iter()
+                .map(|(usage, help)| format!("{usage} - {help}"))
+                .collect();
+            msg.push("/<command> [args] - Execute the command, see `/help`".to_string());
+
+            client.send(msg.join("\n"))
+        },
+        "clients" => clients(client),
+        "kick" => kick(client, args),
+        "plugins" => plugins(client),
+        "shutdown" => shutdown(),
+        name if name.starts_with('/') => {
+            let plugins_manager = plugins_manager();
+
+            let cmd = plugins_manager
+                .commands
+                .iter()
+                .find(|cmd| cmd.name() == name || cmd.aliases().contains(&name))
+                .ok_or_else(|| anyhow!("unknown command `{name}`"))?;
+
+            let started = Instant::now();
+            let result = cmd.execute(client, args.to_vec()).await;
+            metrics::record_command(cmd.name(), started.elapsed());
+
+            result
+        },
+        name => client.send(format!(
+            "Unknown console command `{name}`, type `help` to show commands"
+        )),
+    }
+}
+
+fn clients(client: &Client) -> anyhow::Result<()> {
+    let mut clients: Vec<Client> = CLIENTS.lock().unwrap().values().cloned().collect();
+    clients.sort_by_key(|client| client.id);
+
+    if clients.is_empty() {
+        return client.send("No connected clients");
+    }
+
+    let msg: Vec<String> = clients
+        .iter()
+        .map(|target| {
+            let address = match target.metadata.peer_credentials {
+                Some(credentials) => credentials.to_string(),
+                None => target
+                    .peer_addr()
+                    .map(|addr| addr.to_string())
+                    .unwrap_or_else(|_| "unknown".to_string()),
+            };
+
+            let rooms = rooms::client_rooms(target.id);
+
+            format!(
+                "{id} {name} ({transport}, {address}) account: {account} rooms: {rooms} idle: {idle}",
+                id = target.id,
+                name = target.display_name(),
+                transport = target.metadata.transport,
+                account = target.account.lock().unwrap().as_deref().unwrap_or("none"),
+                rooms = if rooms.is_empty() {
+                    "none".to_string()
+                } else {
+                    rooms.join(", ")
+                },
+                idle = format_duration(target.metadata.idle_for()),
+            )
+        })
+        .collect();
+
+    client.send(msg.join("\n"))
+}
+
+fn kick(client: &Client, args: &[&str]) -> anyhow::Result<()> {
+    let Some((target, reason)) = args.split_first() else {
+        return client.send("Usage: kick <client id|nick> [reason]");
+    };
+
+    let target = match find_client(target) {
+        Some(target) => target,
+        None => return client.send(format!("Unknown client `{target}`")),
+    };
+
+    let reason = Some(reason.join(" ")).filter(|reason| !reason.is_empty());
+
+    moderation::kick(&target, reason.as_deref())?;
+
+    audit::record(
+        &Actor::client(client),
+        "kick",
+        Some(&target.display_name()),
+        reason.as_deref(),
+    );
+
+    client.send(format!("Kicked {}", target.display_name()))
+}
+
+fn plugins(client: &Client) -> anyhow::Result<()> {
+    let plugins_manager = plugins_manager();
+
+    let names = |names: Vec<String>| {
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(", ")
+        }
+    };
+
+    client.send(format!(
+        "plugins: {}\ncommands: {}\nevents: {}",
+        names(
+            plugins_manager
+                .plugins
+                .iter()
+                .map(|plugin| plugin.name().to_string())
+                .collect()
+        ),
+        names(
+            plugins_manager
+                .commands
+                .iter()
+                .map(|cmd| cmd.name().to_string())
+                .collect()
+        ),
+        names(
+            plugins_manager
+                .events
+                .iter()
+                .map(|event| event.event().to_string())
+                .collect()
+        ),
+    ))
+}
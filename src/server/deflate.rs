@@ -0,0 +1,338 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::anyhow;
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+
+/// Name of the WebSocket extension
+pub const PERMESSAGE_DEFLATE: &str = "permessage-deflate";
+
+/// Trailer removed from the compressed messages (RFC 7692, section 7.2.1)
+const TRAILER: [u8; 4] = [0x00, 0x00, 0xFF, 0xFF];
+
+/// Smallest window supported by the compressor (zlib doesn't support 8 bits
+/// for raw deflate streams)
+const MIN_SERVER_WINDOW_BITS: u8 = 9;
+
+/// Configuration of the permessage-deflate extension of the listener
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeflateConfig {
+    /// Reset the compressor of the server after each message
+    pub server_no_context_takeover: bool,
+    /// Ask clients to reset their compressors after each message
+    pub client_no_context_takeover: bool,
+    /// Max size of the LZ77 window used by the server (9-15 bits)
+    pub server_max_window_bits: u8,
+    /// Max size of the LZ77 window asked from clients that support it (8-15 bits)
+    pub client_max_window_bits: u8,
+}
+
+impl Default for DeflateConfig {
+    fn default() -> Self {
+        Self {
+            server_no_context_takeover: false,
+            client_no_context_takeover: false,
+            server_max_window_bits: 15,
+            client_max_window_bits: 15,
+        }
+    }
+}
+
+impl FromStr for DeflateConfig {
+    type Err = anyhow::Error;
+
+    /// Parse the configuration from parameters separated by `;` (the same as
+    /// in the extension header), e.g. `server_no_context_takeover; server_max_window_bits=12`.
+    fn from_str(params: &str) -> anyhow::Result<Self> {
+        let mut config = Self::default();
+
+        for param in params.split(';').map(|param| param.trim()) {
+            let window_bits = |value: &str, min: u8| {
+                value
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|bits| (min..=15).contains(bits))
+                    .ok_or_else(|| anyhow!("window bits must be between {min} and 15"))
+            };
+
+            match param.split_once('=') {
+                None if param.is_empty() => {},
+                None if param == "server_no_context_takeover" => {
+                    config.server_no_context_takeover = true
+                },
+                None if param == "client_no_context_takeover" => {
+                    config.client_no_context_takeover = true
+                },
+                Some(("server_max_window_bits", value)) => {
+                    config.server_max_window_bits = window_bits(value, MIN_SERVER_WINDOW_BITS)?
+                },
+                Some(("client_max_window_bits", value)) => {
+                    config.client_max_window_bits = window_bits(value, 8)?
+                },
+                _ => return Err(anyhow!("unknown permessage-deflate parameter `{param}`")),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Parameters of the permessage-deflate extension negotiated with the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeflateParams {
+    /// The server resets its compressor after each message
+    pub server_no_context_takeover: bool,
+    /// The client resets its compressor after each message
+    pub client_no_context_takeover: bool,
+    /// Size of the LZ77 window used by the server
+    pub server_max_window_bits: u8,
+    /// Size of the LZ77 window used by the client (`None` if the client
+    /// doesn't support limiting it)
+    pub client_max_window_bits: Option<u8>,
+}
+
+/// Parameters of the extension offered by the client
+#[derive(Debug, Default)]
+struct Offer {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+    server_max_window_bits: Option<u8>,
+    /// `Some(None)` if the parameter was sent without a value
+    client_max_window_bits: Option<Option<u8>>,
+}
+
+impl DeflateConfig {
+    /// Negotiate the extension from the `Sec-WebSocket-Extensions` header sent
+    /// by the client. The first offer the server can accept is used, returns
+    /// `None` if the client didn't offer the extension or no offer is acceptable.
+    pub fn negotiate(&self, header: &str) -> Option<DeflateParams> {
+        header
+            .split(',')
+            .filter_map(parse_offer)
+            .find_map(|offer| self.accept(offer))
+    }
+
+    /// Accept the offer with the configuration of the server.
+    fn accept(&self, offer: Offer) -> Option<DeflateParams> {
+        let server_max_window_bits = offer
+            .server_max_window_bits
+            .unwrap_or(15)
+            .min(self.server_max_window_bits);
+
+        // the client asked for a window the compressor can't use
+        if server_max_window_bits < MIN_SERVER_WINDOW_BITS {
+            return None;
+        }
+
+        // the window of the client can be limited only if it supports it
+        let client_max_window_bits = offer
+            .client_max_window_bits
+            .map(|bits| bits.unwrap_or(15).min(self.client_max_window_bits));
+
+        Some(DeflateParams {
+            server_no_context_takeover: offer.server_no_context_takeover
+                || self.server_no_context_takeover,
+            client_no_context_takeover: offer.client_no_context_takeover
+                || self.client_no_context_takeover,
+            server_max_window_bits,
+            client_max_window_bits,
+        })
+    }
+}
+
+/// Parse the extension offer, returns `None` if it isn't permessage-deflate or
+/// has invalid parameters.
+fn parse_offer(offer: &str) -> Option<Offer> {
+    let mut params = offer.split(';').map(|param| param.trim());
+
+    if params.next()? != PERMESSAGE_DEFLATE {
+        return None;
+    }
+
+    let mut offer = Offer::default();
+
+    for param in params {
+        let (name, value) = match param.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (param, None),
+        };
+
+        let window_bits = || {
+            value
+                .and_then(|value| value.parse::<u8>().ok())
+                .filter(|bits| (8..=15).contains(bits))
+        };
+
+        // duplicated parameters make the offer invalid
+        match (name, value) {
+            ("server_no_context_takeover", None) if !offer.server_no_context_takeover => {
+                offer.server_no_context_takeover = true
+            },
+            ("client_no_context_takeover", None) if !offer.client_no_context_takeover => {
+                offer.client_no_context_takeover = true
+            },
+            ("server_max_window_bits", Some(_)) if offer.server_max_window_bits.is_none() => {
+                offer.server_max_window_bits = Some(window_bits()?)
+            },
+            ("client_max_window_bits", None) if offer.client_max_window_bits.is_none() => {
+                offer.client_max_window_bits = Some(None)
+            },
+            ("client_max_window_bits", Some(_)) if offer.client_max_window_bits.is_none() => {
+                offer.client_max_window_bits = Some(Some(window_bits()?))
+            },
+            _ => return None,
+        }
+    }
+
+    Some(offer)
+}
+
+impl fmt::Display for DeflateParams {
+    /// Formats the parameters as the value of the `Sec-WebSocket-Extensions` response header.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{PERMESSAGE_DEFLATE}")?;
+
+        if self.server_no_context_takeover {
+            write!(f, "; server_no_context_takeover")?;
+        }
+
+        if self.client_no_context_takeover {
+            write!(f, "; client_no_context_takeover")?;
+        }
+
+        if self.server_max_window_bits < 15 {
+            write!(
+                f,
+                "; server_max_window_bits={}",
+                self.server_max_window_bits
+            )?;
+        }
+
+        if let Some(bits) = self.client_max_window_bits.filter(|bits| *bits < 15) {
+            write!(f, "; client_max_window_bits={bits}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compressor of the messages sent to the client
+#[derive(Debug)]
+pub struct MessageCompressor {
+    compress: Compress,
+    no_context_takeover: bool,
+}
+
+impl MessageCompressor {
+    /// Create the compressor with the negotiated parameters.
+    pub fn new(params: &DeflateParams) -> Self {
+        Self {
+            compress: Compress::new_with_window_bits(
+                Compression::default(),
+                false,
+                params.server_max_window_bits,
+            ),
+            no_context_takeover: params.server_no_context_takeover,
+        }
+    }
+
+    /// Compress the payload of the message.
+    pub fn compress(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut output = Vec::with_capacity(data.len() / 2 + 64);
+        let mut input = data;
+
+        loop {
+            let total_in = self.compress.total_in();
+
+            self.compress
+                .compress_vec(input, &mut output, FlushCompress::Sync)?;
+
+            input = &input[(self.compress.total_in() - total_in) as usize..];
+
+            // the flush is complete if the output wasn't filled up
+            if input.is_empty() && output.len() < output.capacity() {
+                break;
+            }
+
+            output.reserve(output.capacity().max(64));
+        }
+
+        if output.ends_with(&TRAILER) {
+            output.truncate(output.len() - TRAILER.len());
+        }
+
+        if self.no_context_takeover {
+            self.compress.reset();
+        }
+
+        Ok(output)
+    }
+}
+
+/// Decompressor of the messages received from the client
+#[derive(Debug)]
+pub struct MessageDecompressor {
+    decompress: Decompress,
+    no_context_takeover: bool,
+}
+
+impl MessageDecompressor {
+    /// Create the decompressor with the negotiated parameters.
+    pub fn new(params: &DeflateParams) -> Self {
+        Self {
+            // the largest window can decompress data compressed with any window
+            decompress: Decompress::new_with_window_bits(false, 15),
+            no_context_takeover: params.client_no_context_takeover,
+        }
+    }
+
+    /// Decompress the payload of the message, returns an error if the
+    /// decompressed message is longer than `max_len`.
+    pub fn decompress(&mut self, data: &[u8], max_len: usize) -> anyhow::Result<Vec<u8>> {
+        let input = [data, &TRAILER].concat();
+        let mut input = &input[..];
+
+        let mut output = Vec::with_capacity((data.len() * 4).min(max_len) + 64);
+
+        loop {
+            let (total_in, total_out) = (self.decompress.total_in(), self.decompress.total_out());
+
+            let status =
+                self.decompress
+                    .decompress_vec(input, &mut output, FlushDecompress::Sync)?;
+
+            input = &input[(self.decompress.total_in() - total_in) as usize..];
+
+            if output.len() > max_len {
+                return Err(anyhow!("message is too long"));
+            }
+
+            // the message ended with a final block, the next one starts a new stream
+            if status == Status::StreamEnd {
+                self.decompress.reset(false);
+                break;
+            }
+
+            let output_full = output.len() == output.capacity();
+
+            // all input was decompressed and nothing is buffered in the decompressor
+            if input.is_empty() && !output_full {
+                break;
+            }
+
+            if !output_full
+                && self.decompress.total_in() == total_in
+                && self.decompress.total_out() == total_out
+            {
+                return Err(anyhow!("invalid compressed message"));
+            }
+
+            output.reserve(output.capacity().max(64));
+        }
+
+        if self.no_context_takeover {
+            self.decompress.reset(false);
+        }
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// Type-safe storage of values attached to the client by plugins.
+///
+/// Values are stored by their type, so every plugin can use its own types
+/// without collisions with keys used by other plugins.
+///
+/// ```
+/// # use servers::server::Extensions;
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct Score(u32);
+///
+/// let extensions = Extensions::default();
+///
+/// extensions.insert(Score(1));
+/// extensions.with_mut(|score: &mut Score| score.0 += 1);
+///
+/// assert_eq!(extensions.get::<Score>(), Some(Score(2)));
+/// ```
+#[derive(Clone, Default)]
+pub struct Extensions {
+    map: Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>>,
+}
+
+impl Extensions {
+    /// Inserts a value, returns the previous value of the type.
+    pub fn insert<T>(&self, value: T) -> Option<T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.map
+            .lock()
+            .unwrap()
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+
+    /// Returns a copy of the value of the type.
+    pub fn get<T>(&self) -> Option<T>
+    where
+        T: Any + Send + Sync + Clone,
+    {
+        self.map
+            .lock()
+            .unwrap()
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref::<T>())
+            .cloned()
+    }
+
+    /// Calls the function with a mutable reference to the value of the type.
+    /// Returns `None` if there is no value of the type.
+    pub fn with_mut<T, F, R>(&self, f: F) -> Option<R>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce(&mut T) -> R,
+    {
+        self.map
+            .lock()
+            .unwrap()
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut::<T>())
+            .map(f)
+    }
+
+    /// Removes the value of the type and returns it.
+    pub fn remove<T>(&self) -> Option<T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.map
+            .lock()
+            .unwrap()
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+    }
+
+    /// Returns `true` if there is a value of the type.
+    pub fn contains<T>(&self) -> bool
+    where
+        T: Any + Send + Sync,
+    {
+        self.map.lock().unwrap().contains_key(&TypeId::of::<T>())
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.lock().unwrap().len())
+            .finish()
+    }
+}
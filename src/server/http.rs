@@ -0,0 +1,237 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::{admin, metrics};
+
+/// Max size of the request line and headers
+pub const MAX_HEAD_LEN: usize = 8192;
+
+/// Max size of the request body
+pub const MAX_BODY_LEN: usize = 65536;
+
+/// How long to wait for the request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Request received by the HTTP listener
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// Method of the request (e.g. `GET`)
+    pub method: String,
+    /// URL path of the request
+    pub path: String,
+    /// Query string of the request URL
+    pub query: Option<String>,
+    /// Headers of the request by their lowercase names
+    pub headers: HashMap<String, String>,
+    /// Body of the request
+    pub body: Vec<u8>,
+    /// Address of the HTTP client
+    pub peer_addr: Option<SocketAddr>,
+}
+
+/// Response of the HTTP listener
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// Status code of the response
+    pub status: u16,
+    /// Value of the `Content-Type` header
+    pub content_type: &'static str,
+    /// Body of the response
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// Create a plain text response.
+    pub fn text<S>(status: u16, body: S) -> Self
+    where
+        S: ToString,
+    {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.to_string(),
+        }
+    }
+
+    /// Create a JSON response.
+    pub fn json<T>(status: u16, body: &T) -> Self
+    where
+        T: Serialize,
+    {
+        Self {
+            status,
+            content_type: "application/json",
+            // serializing types without maps with non-string keys can't fail
+            body: serde_json::to_string(body).unwrap(),
+        }
+    }
+
+    /// Returns the reason phrase of the status code.
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            500 => "Internal Server Error",
+            503 => "Service Unavailable",
+            _ => "",
+        }
+    }
+}
+
+/// Serve HTTP requests (health checks, metrics and the admin API) on the listener.
+pub fn start_http(listener: TcpListener) {
+    for stream in listener.incoming() {
+        // errors like too many open files are transient, the server keeps running
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                error!("Failed to accept HTTP connection: {}", err);
+                continue;
+            },
+        };
+
+        thread::spawn(move || {
+            if let Err(err) = handle_http(stream) {
+                error!("Failed to handle HTTP request: {}", err);
+            }
+        });
+    }
+}
+
+/// Read the request, route it and write the response.
+fn handle_http(mut stream: TcpStream) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let response = match read_request(&mut stream) {
+        Ok(req) => {
+            info!("HTTP request {} {}", req.method, req.path);
+
+            route(&req)
+        },
+        Err(err) => HttpResponse::text(400, err),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+
+    Ok(())
+}
+
+/// Read the request line, headers and body of the request.
+fn read_request(stream: &mut TcpStream) -> anyhow::Result<HttpRequest> {
+    let mut buf = Vec::new();
+
+    while !buf.windows(4).any(|window| window == b"\r\n\r\n") {
+        if buf.len() > MAX_HEAD_LEN {
+            return Err(anyhow!("request is too large"));
+        }
+
+        let mut chunk = [0; 1024];
+        let len = stream.read(&mut chunk)?;
+
+        if len == 0 {
+            return Err(anyhow!("connection closed before the request ended"));
+        }
+
+        buf.extend(&chunk[..len]);
+    }
+
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut req = httparse::Request::new(&mut headers);
+
+    let head_len = match req.parse(&buf)? {
+        httparse::Status::Complete(len) => len,
+        httparse::Status::Partial => return Err(anyhow!("incomplete request")),
+    };
+
+    let body_len = match req
+        .headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+    {
+        Some(header) => std::str::from_utf8(header.value)?
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| anyhow!("invalid Content-Length header"))?,
+        None => 0,
+    };
+
+    if body_len > MAX_BODY_LEN {
+        return Err(anyhow!("request body is too large"));
+    }
+
+    // the rest of the body wasn't read with the headers
+    let mut body = buf[head_len..].to_vec();
+    if body.len() < body_len {
+        let mut rest = vec![0; body_len - body.len()];
+        stream.read_exact(&mut rest)?;
+
+        body.extend(rest);
+    }
+    body.truncate(body_len);
+
+    let target = req.path.ok_or_else(|| anyhow!("missing request path"))?;
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (target, None),
+    };
+
+    Ok(HttpRequest {
+        method: req.method.unwrap_or_default().to_string(),
+        path: path.to_string(),
+        query,
+        headers: req
+            .headers
+            .iter()
+            .filter_map(|header| {
+                let value = std::str::from_utf8(header.value).ok()?;
+
+                Some((header.name.to_ascii_lowercase(), value.to_string()))
+            })
+            .collect(),
+        body,
+        peer_addr: stream.peer_addr().ok(),
+    })
+}
+
+/// Returns the response to the request.
+fn route(req: &HttpRequest) -> HttpResponse {
+    if req.path == "/admin" || req.path.starts_with("/admin/") {
+        return admin::handle(req);
+    }
+
+    if req.method != "GET" {
+        return HttpResponse::text(405, "method not allowed");
+    }
+
+    match req.path.as_str() {
+        "/healthz" => HttpResponse::text(200, "ok"),
+        "/readyz" if metrics::is_ready() => HttpResponse::text(200, "ready"),
+        "/readyz" => HttpResponse::text(503, "not ready"),
+        "/metrics" => HttpResponse {
+            status: 200,
+            content_type: "text/plain; version=0.0.4; charset=utf-8",
+            body: metrics::render(),
+        },
+        _ => HttpResponse::text(404, "not found"),
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+
+/// Message sent or received by the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// UTF-8 text (commands and chat messages)
+    Text(String),
+    /// Binary data
+    Binary(Vec<u8>),
+}
+
+impl Message {
+    /// Returns the message as bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Text(text) => text.as_bytes(),
+            Self::Binary(data) => data,
+        }
+    }
+
+    /// Returns the text of the message (`None` for binary messages).
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            Self::Binary(_) => None,
+        }
+    }
+
+    /// Returns `true` if the message is binary.
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Self::Binary(_))
+    }
+
+    /// Returns the length of the message in bytes.
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    /// Returns `true` if the message is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decode the bytes as a text message, bytes which aren't valid UTF-8 are
+    /// a binary message.
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        match String::from_utf8(data) {
+            Ok(text) => Self::Text(text),
+            Err(err) => Self::Binary(err.into_bytes()),
+        }
+    }
+}
+
+impl From<String> for Message {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Message {
+    fn from(data: Vec<u8>) -> Self {
+        Self::Binary(data)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => write!(f, "{text}"),
+            Self::Binary(data) => write!(f, "<binary data, {} bytes>", data.len()),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use super::{DeflateParams, WebSocketProtocol};
+use crate::metrics;
+
+/// Transport used by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// Raw TCP connection
+    TCP,
+    /// WebSocket connection
+    WebSocket,
+    /// Unix domain socket connection
+    Unix,
+    /// UDP pseudo-session
+    UDP,
+    /// Admin console on the standard input of the server
+    Console,
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TCP => write!(f, "tcp"),
+            Self::WebSocket => write!(f, "websocket"),
+            Self::Unix => write!(f, "unix"),
+            Self::UDP => write!(f, "udp"),
+            Self::Console => write!(f, "console"),
+        }
+    }
+}
+
+/// Credentials of the process connected using a Unix domain socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCredentials {
+    /// User ID of the process
+    pub uid: u32,
+    /// Group ID of the process
+    pub gid: u32,
+    /// Process ID (not available on all platforms)
+    pub pid: Option<i32>,
+}
+
+impl fmt::Display for PeerCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "uid={} gid={}", self.uid, self.gid)?;
+
+        if let Some(pid) = self.pid {
+            write!(f, " pid={pid}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Request of the WebSocket handshake
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HandshakeRequest {
+    /// URL path of the request
+    pub path: String,
+    /// Query string of the request URL (without the `token` parameter)
+    pub query: Option<String>,
+    /// Headers of the request by their lowercase names (values of repeated
+    /// headers are joined with `, `), without `Authorization`, `Cookie` and
+    /// `Sec-WebSocket-Key`
+    pub headers: HashMap<String, String>,
+    /// Cookies sent in the `Cookie` headers
+    pub cookies: HashMap<String, String>,
+    /// Subprotocol negotiated using the `Sec-WebSocket-Protocol` header
+    pub protocol: Option<WebSocketProtocol>,
+    /// Parameters of the negotiated permessage-deflate extension
+    pub deflate: Option<DeflateParams>,
+}
+
+impl HandshakeRequest {
+    /// Returns the value of the header (the name is case insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(|value| value.as_str())
+    }
+
+    /// Returns the value of the cookie.
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies.get(name).map(|value| value.as_str())
+    }
+
+    /// Remove the credentials used to authenticate the client (and the handshake
+    /// key), so they aren't kept for the whole connection.
+    pub(crate) fn remove_credentials(&mut self) {
+        for name in ["authorization", "cookie", "sec-websocket-key"] {
+            self.headers.remove(name);
+        }
+
+        self.query = self.query.take().and_then(|query| {
+            let query: Vec<&str> = query
+                .split('&')
+                .filter(|param| *param != "token" && !param.starts_with("token="))
+                .collect();
+
+            Some(query.join("&")).filter(|query| !query.is_empty())
+        });
+    }
+
+    /// Returns the value of the query parameter.
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .as_deref()?
+            .split('&')
+            .find_map(|param| param.strip_prefix(name)?.strip_prefix('='))
+    }
+}
+
+/// Metadata and traffic counters of the client connection
+#[derive(Debug)]
+pub struct ClientMetadata {
+    /// Time when the client connected
+    pub connected_at: SystemTime,
+    /// Transport used by the client
+    pub transport: Transport,
+    /// Credentials of the peer process (only for Unix domain socket connections)
+    pub peer_credentials: Option<PeerCredentials>,
+    /// Request of the WebSocket handshake (only for WebSocket connections)
+    pub handshake: Option<HandshakeRequest>,
+    /// Time of the last message received from the client
+    last_activity: Mutex<Instant>,
+    /// Number of messages received from the client
+    messages_in: AtomicU64,
+    /// Number of messages sent to the client
+    messages_out: AtomicU64,
+    /// Number of bytes received from the client
+    bytes_in: AtomicU64,
+    /// Number of bytes sent to the client
+    bytes_out: AtomicU64,
+    /// Number of commands executed by the client
+    commands: AtomicU64,
+    /// Number of errors while handling messages of the client
+    errors: AtomicU64,
+}
+
+/// Snapshot of the client traffic counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    /// Number of messages received from the client
+    pub messages_in: u64,
+    /// Number of messages sent to the client
+    pub messages_out: u64,
+    /// Number of bytes received from the client
+    pub bytes_in: u64,
+    /// Number of bytes sent to the client
+    pub bytes_out: u64,
+    /// Number of commands executed by the client
+    pub commands: u64,
+    /// Number of errors while handling messages of the client
+    pub errors: u64,
+}
+
+impl ClientMetadata {
+    /// Create metadata of a new connection
+    pub fn new(transport: Transport) -> Self {
+        Self {
+            connected_at: SystemTime::now(),
+            transport,
+            peer_credentials: None,
+            handshake: None,
+            last_activity: Mutex::new(Instant::now()),
+            messages_in: AtomicU64::new(0),
+            messages_out: AtomicU64::new(0),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            commands: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns how long the client is connected.
+    pub fn connected_for(&self) -> Duration {
+        self.connected_at.elapsed().unwrap_or_default()
+    }
+
+    /// Returns how long ago the client sent the last message.
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity.lock().unwrap().elapsed()
+    }
+
+    /// Returns a snapshot of the traffic counters.
+    pub fn stats(&self) -> ClientStats {
+        ClientStats {
+            messages_in: self.messages_in.load(Ordering::Relaxed),
+            messages_out: self.messages_out.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            commands: self.commands.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Record a message received from the client.
+    pub(crate) fn record_in(&self, bytes: usize) {
+        metrics::record_message_in(self.transport);
+        self.messages_in.fetch_add(1, Ordering::Relaxed);
+        self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// Record a message sent to the client.
+    pub(crate) fn record_out(&self, bytes: usize) {
+        metrics::record_message_out(self.transport);
+        self.messages_out.fetch_add(1, Ordering::Relaxed);
+        self.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Record a command executed by the client.
+    pub(crate) fn record_command(&self) {
+        self.commands.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an error while handling a message of the client.
+    pub(crate) fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Format the duration in a human readable form (e.g. `1h 5m 3s`).
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    let (days, hours, mins, secs) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+
+    let parts: Vec<String> = [(days, "d"), (hours, "h"), (mins, "m")]
+        .into_iter()
+        .skip_while(|(value, _)| *value == 0)
+        .map(|(value, unit)| format!("{value}{unit}"))
+        .chain([format!("{secs}s")])
+        .collect();
+
+    parts.join(" ")
+}
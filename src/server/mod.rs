@@ -1,7 +1,39 @@
 //! Server infrastructure.
 
 mod client;
+mod clients;
+mod config;
+mod console;
+mod deflate;
+mod extensions;
+mod http;
+mod message;
+mod metadata;
+mod outbound;
+mod proxy;
 mod run;
+mod sessions;
+mod sniff;
+mod udp;
+#[cfg(unix)]
+mod unix;
+mod websocket;
 
 pub use client::*;
+pub use clients::*;
+pub use config::*;
+pub use console::*;
+pub use deflate::*;
+pub use extensions::*;
+pub use http::*;
+pub use message::*;
+pub use metadata::*;
+pub use outbound::*;
+pub use proxy::*;
 pub use run::*;
+pub use sessions::*;
+pub use sniff::*;
+pub use udp::*;
+#[cfg(unix)]
+pub use unix::*;
+pub use websocket::*;
@@ -0,0 +1,188 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    str::FromStr,
+    sync::{Arc, Condvar, Mutex},
+};
+
+use anyhow::anyhow;
+
+use super::Message;
+
+/// What to do when the outbound queue of the client is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued message
+    #[default]
+    DropOldest,
+    /// Disconnect the slow client
+    Disconnect,
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "drop-oldest" => Ok(Self::DropOldest),
+            "disconnect" => Ok(Self::Disconnect),
+            _ => Err(anyhow!(
+                "invalid overflow policy `{s}` (expected `drop-oldest` or `disconnect`)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DropOldest => write!(f, "drop-oldest"),
+            Self::Disconnect => write!(f, "disconnect"),
+        }
+    }
+}
+
+/// Result of pushing a message to the [SendQueue]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Enqueued {
+    /// The message was queued
+    Queued,
+    /// The message was queued and the oldest message was dropped
+    DroppedOldest,
+}
+
+/// Bounded queue of messages waiting to be written to the client by its writer thread
+#[derive(Debug, Clone)]
+pub struct SendQueue {
+    inner: Arc<(Mutex<SendQueueState>, Condvar)>,
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+#[derive(Debug, Default)]
+struct SendQueueState {
+    messages: VecDeque<Message>,
+    /// No more messages are accepted, the writer stops after draining the queue
+    closed: bool,
+    /// The connection is broken, queued messages are discarded
+    failed: bool,
+    /// The writer is writing a message taken from the queue
+    writing: bool,
+}
+
+impl SendQueue {
+    /// Create a new queue with the capacity and the overflow policy.
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            inner: Arc::new((Mutex::new(SendQueueState::default()), Condvar::new())),
+            capacity: capacity.max(1),
+            policy,
+        }
+    }
+
+    /// Push a message to the queue without blocking. Returns an error if the
+    /// queue is closed or full with the [OverflowPolicy::Disconnect] policy.
+    pub fn push(&self, msg: Message) -> anyhow::Result<Enqueued> {
+        let (state, condvar) = &*self.inner;
+        let mut state = state.lock().unwrap();
+
+        if state.closed || state.failed {
+            return Err(anyhow!("disconnected"));
+        }
+
+        let mut enqueued = Enqueued::Queued;
+
+        if state.messages.len() >= self.capacity {
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    state.messages.pop_front();
+                    enqueued = Enqueued::DroppedOldest;
+                },
+                OverflowPolicy::Disconnect => {
+                    state.failed = true;
+                    state.messages.clear();
+                    condvar.notify_all();
+
+                    return Err(anyhow!("send queue full"));
+                },
+            }
+        }
+
+        state.messages.push_back(msg);
+        condvar.notify_all();
+
+        Ok(enqueued)
+    }
+
+    /// Wait for the next message. Returns `None` when the queue is closed and
+    /// drained or the connection failed.
+    pub(crate) fn pop(&self) -> Option<Message> {
+        let (state, condvar) = &*self.inner;
+        let mut state = state.lock().unwrap();
+
+        state.writing = false;
+        condvar.notify_all();
+
+        loop {
+            if state.failed {
+                return None;
+            }
+
+            if let Some(msg) = state.messages.pop_front() {
+                state.writing = true;
+                return Some(msg);
+            }
+
+            if state.closed {
+                return None;
+            }
+
+            state = condvar.wait(state).unwrap();
+        }
+    }
+
+    /// Wait until all queued messages are written (or the connection is closed).
+    pub fn wait_idle(&self) {
+        let (state, condvar) = &*self.inner;
+        let mut state = state.lock().unwrap();
+
+        while (state.writing || !state.messages.is_empty()) && !state.failed && !state.closed {
+            state = condvar.wait(state).unwrap();
+        }
+    }
+
+    /// Stop accepting messages, already queued messages are still written.
+    pub fn close(&self) {
+        let (state, condvar) = &*self.inner;
+
+        state.lock().unwrap().closed = true;
+        condvar.notify_all();
+    }
+
+    /// Mark the connection as broken and discard queued messages.
+    pub(crate) fn fail(&self) {
+        let (state, condvar) = &*self.inner;
+
+        let mut state = state.lock().unwrap();
+        state.failed = true;
+        state.writing = false;
+        state.messages.clear();
+
+        condvar.notify_all();
+    }
+
+    /// Returns `true` if the connection failed (e.g. the client was too slow).
+    pub fn is_failed(&self) -> bool {
+        self.inner.0.lock().unwrap().failed
+    }
+
+    /// Returns the number of queued messages.
+    pub fn len(&self) -> usize {
+        self.inner.0.lock().unwrap().messages.len()
+    }
+
+    /// Returns `true` if no messages are queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
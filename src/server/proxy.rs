@@ -0,0 +1,172 @@
+use std::{
+    io::Read,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+
+/// How long to wait for the PROXY protocol header.
+pub const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Max length of the PROXY protocol v1 header (including CRLF)
+const V1_MAX_LEN: usize = 107;
+
+/// Signature of the PROXY protocol v2 header
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Read the PROXY protocol (v1 or v2) header sent by the proxy before the data
+/// of the client. Returns the address of the client, or `None` if the proxy
+/// didn't send it (e.g. health checks of the proxy).
+///
+/// Only the header is consumed from the stream.
+pub fn read_proxy_header(mut stream: &TcpStream) -> anyhow::Result<Option<SocketAddr>> {
+    stream.set_read_timeout(Some(PROXY_HEADER_TIMEOUT))?;
+
+    let mut prefix = [0; 5];
+    stream.read_exact(&mut prefix)?;
+
+    let addr = if &prefix == b"PROXY" {
+        read_v1(stream, prefix)?
+    } else if prefix == V2_SIGNATURE[..5] {
+        read_v2(stream)?
+    } else {
+        return Err(anyhow!("missing PROXY protocol header"));
+    };
+
+    stream.set_read_timeout(None)?;
+
+    Ok(addr)
+}
+
+/// Read the rest of the text header, e.g. `PROXY TCP4 192.0.2.1 192.0.2.2 56324 9999\r\n`.
+fn read_v1(mut stream: &TcpStream, prefix: [u8; 5]) -> anyhow::Result<Option<SocketAddr>> {
+    let mut header = prefix.to_vec();
+
+    // read byte by byte, so no data of the client is consumed
+    while !header.ends_with(b"\r\n") {
+        if header.len() >= V1_MAX_LEN {
+            return Err(anyhow!("PROXY protocol header is too long"));
+        }
+
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte)?;
+
+        header.push(byte[0]);
+    }
+
+    let header = std::str::from_utf8(&header[..header.len() - 2])?;
+
+    parse_v1(header)
+}
+
+/// Parse the text header without the trailing CRLF.
+fn parse_v1(header: &str) -> anyhow::Result<Option<SocketAddr>> {
+    let invalid = || anyhow!("invalid PROXY protocol header `{header}`");
+
+    let parts: Vec<&str> = header.split(' ').collect();
+
+    match parts.get(1).copied() {
+        Some("UNKNOWN") => Ok(None),
+        Some("TCP4" | "TCP6") if parts.len() == 6 => {
+            let ip: IpAddr = parts[2].parse().map_err(|_| invalid())?;
+            let port: u16 = parts[4].parse().map_err(|_| invalid())?;
+
+            if ip.is_ipv4() != (parts[1] == "TCP4") {
+                return Err(invalid());
+            }
+
+            Ok(Some(SocketAddr::new(ip, port)))
+        },
+        _ => Err(invalid()),
+    }
+}
+
+/// Read the rest of the binary header.
+fn read_v2(mut stream: &TcpStream) -> anyhow::Result<Option<SocketAddr>> {
+    let mut header = [0; 11];
+    stream.read_exact(&mut header)?;
+
+    if header[..7] != V2_SIGNATURE[5..] {
+        return Err(anyhow!("invalid PROXY protocol v2 signature"));
+    }
+
+    let (version_command, family) = (header[7], header[8]);
+    let len = u16::from_be_bytes([header[9], header[10]]) as usize;
+
+    if version_command >> 4 != 2 {
+        return Err(anyhow!("unsupported PROXY protocol version"));
+    }
+
+    // addresses and TLVs, TLVs are ignored
+    let mut payload = vec![0; len];
+    stream.read_exact(&mut payload)?;
+
+    match version_command & 0x0F {
+        // LOCAL command, the connection was made by the proxy itself
+        0x0 => Ok(None),
+        0x1 => parse_v2_addr(family, &payload),
+        command => Err(anyhow!("unsupported PROXY protocol command {command:#x}")),
+    }
+}
+
+/// Parse the source address of the binary header.
+fn parse_v2_addr(family: u8, payload: &[u8]) -> anyhow::Result<Option<SocketAddr>> {
+    let too_short = || anyhow!("PROXY protocol v2 header is too short");
+
+    let addr = match family >> 4 {
+        // AF_INET
+        0x1 => {
+            let addr = payload.get(..12).ok_or_else(too_short)?;
+
+            let ip = Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]);
+            let port = u16::from_be_bytes([addr[8], addr[9]]);
+
+            SocketAddr::new(ip.into(), port)
+        },
+        // AF_INET6
+        0x2 => {
+            let addr = payload.get(..36).ok_or_else(too_short)?;
+
+            let ip: [u8; 16] = addr[..16].try_into()?;
+            let port = u16::from_be_bytes([addr[32], addr[33]]);
+
+            SocketAddr::new(Ipv6Addr::from(ip).into(), port)
+        },
+        // AF_UNSPEC and AF_UNIX don't have an IP address
+        _ => return Ok(None),
+    };
+
+    Ok(Some(addr))
+}
+
+/// Find the address of the client in the `X-Forwarded-For` header. Addresses
+/// are checked from the right (the closest proxy) and the first address which
+/// isn't a trusted proxy is the client.
+pub fn forwarded_for<F>(header: &str, peer: IpAddr, is_trusted: F) -> Option<IpAddr>
+where
+    F: Fn(IpAddr) -> bool,
+{
+    let mut addr = peer;
+
+    for entry in header.rsplit(',') {
+        if !is_trusted(addr) {
+            break;
+        }
+
+        let entry = entry.trim();
+
+        // some proxies add the port to the address
+        let Some(ip) = entry
+            .parse::<IpAddr>()
+            .ok()
+            .or_else(|| entry.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        else {
+            break;
+        };
+
+        addr = ip;
+    }
+
+    (addr != peer).then_some(addr)
+}
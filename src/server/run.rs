@@ -1,42 +1,160 @@
-use std::{net::TcpListener, thread};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+    sync::{Arc, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
 
 use anyhow::anyhow;
 use async_std::task;
 use futures::join;
 use lazy_static::lazy_static;
-use tracing::{error, info, span, Level};
-
+use tracing::{debug, error, field, info, span, Level, Span};
+
+#[cfg(unix)]
+use super::bind_unix;
+use super::{
+    config::{Config, CONFIG},
+    read_proxy_header,
+    sessions::{self, detach_session, end_session, new_session, remove_expired_sessions},
+    sniff_protocol, start_console, start_http, udp, SniffedProtocol, Transport, MAX_PACKET_LEN,
+};
 use crate::{
+    auth,
+    history::HISTORY,
+    metrics, moderation,
+    permissions::{self, POLICY},
     plugins::{
         self,
         prelude::{EventData, EventType},
         PluginsManagerType,
     },
-    server::Client,
+    rooms, routes,
+    server::{Client, Message},
     CLIENTS, CLIENT_NEXT,
 };
 
 /// Plugins directory.
 pub const PLUGINS_DIR: &str = "plugins";
 
+/// How often idle clients are checked.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often expired sessions are removed.
+const SESSION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long clients have to receive the shutdown message before the server exits.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
 lazy_static! {
     /// Plugin manager, where you can find loaded plugins, commands and events
-    pub static ref PLUGINS_MANAGER: PluginsManagerType =
-        plugins::loader(PLUGINS_DIR).expect("failed to load plugins");
+    /// (replaced when the plugins directory is rescanned)
+    pub static ref PLUGINS_MANAGER: RwLock<PluginsManagerType> =
+        RwLock::new(plugins::loader(PLUGINS_DIR).expect("failed to load plugins"));
+}
+
+/// Returns the current plugins manager.
+pub fn plugins_manager() -> PluginsManagerType {
+    PLUGINS_MANAGER.read().unwrap().clone()
+}
+
+/// Rescan the [PLUGINS_DIR] for new plugins and replace the plugins manager,
+/// see [plugins::loader]. Messages received after the rescan are handled by the
+/// new plugins manager. Changed plugin files aren't reloaded and plugins of
+/// removed files stay loaded.
+pub fn rescan_plugins() -> anyhow::Result<PluginsManagerType> {
+    let plugins_manager = plugins::loader(PLUGINS_DIR)?;
+
+    *PLUGINS_MANAGER.write().unwrap() = plugins_manager.clone();
+
+    info!(
+        "Rescanned plugins: {} plugins, {} commands, {} events",
+        plugins_manager.plugins.len(),
+        plugins_manager.commands.len(),
+        plugins_manager.events.len()
+    );
+
+    Ok(plugins_manager)
 }
 
 /// Start servers
-pub fn run(tcp_host: String, ws_host: String) -> anyhow::Result<()> {
-    info!("Loaded {} plugins", PLUGINS_MANAGER.plugins.len());
-    info!("Loaded {} commands", PLUGINS_MANAGER.commands.len());
-    info!("Loaded {} events", PLUGINS_MANAGER.events.len());
+pub fn run(config: Config) -> anyhow::Result<()> {
+    let tcp_host = config.tcp_host.clone();
+    let ws_host = config.ws_host.clone();
+
+    if config.proxy_protocol && config.trusted_proxies.is_empty() {
+        return Err(anyhow!(
+            "PROXY protocol requires at least one trusted proxy"
+        ));
+    }
+
+    *CONFIG.write().unwrap() = config;
+
+    // the HTTP server is started first, so `/readyz` reports the startup
+    if let Some(host) = CONFIG.read().unwrap().http_host.clone() {
+        let listener = TcpListener::bind(host)?;
+
+        thread::spawn(move || start_http(listener));
+    }
+
+    let plugins_manager = plugins_manager();
+
+    info!("Loaded {} plugins", plugins_manager.plugins.len());
+    info!("Loaded {} commands", plugins_manager.commands.len());
+    info!("Loaded {} events", plugins_manager.events.len());
+    info!("Loaded {} roles", POLICY.read().unwrap().roles.len());
+    info!("History storage: {}", HISTORY.read().unwrap().storage);
+
+    if let Some(timeout) = CONFIG.read().unwrap().idle_timeout {
+        thread::spawn(move || disconnect_idle_clients(timeout));
+    }
+
+    if CONFIG.read().unwrap().session_grace.is_some() {
+        thread::spawn(|| loop {
+            thread::sleep(SESSION_CHECK_INTERVAL);
+            remove_expired_sessions();
+        });
+    }
+
+    if let Some(host) = CONFIG.read().unwrap().single_port_host.clone() {
+        let listener = TcpListener::bind(host)?;
+
+        thread::spawn(move || start_single_port(listener));
+    }
+
+    if let Some(host) = CONFIG.read().unwrap().udp_host.clone() {
+        let socket = UdpSocket::bind(host)?;
+
+        thread::spawn(move || start_udp(socket));
+    }
+
+    #[cfg(unix)]
+    if let Some(path) = CONFIG.read().unwrap().unix_socket.clone() {
+        let listener = bind_unix(&path, CONFIG.read().unwrap().unix_socket_mode)?;
+
+        info!("Listening on unix socket `{}`", path.display());
+
+        thread::spawn(move || start_unix(listener));
+    }
+
+    let tcp_listener = TcpListener::bind(tcp_host)?;
+    let ws_listener = TcpListener::bind(ws_host)?;
+
+    // plugins are loaded and all listeners are bound
+    metrics::set_ready(true);
+
+    if CONFIG.read().unwrap().console {
+        thread::spawn(start_console);
+    }
 
     let tcp_child = task::spawn(async move {
-        start_tcp(tcp_host).await.unwrap();
+        start_tcp(tcp_listener).await.unwrap();
     });
 
     let ws_child = task::spawn(async move {
-        start_websocket(ws_host).await.unwrap();
+        start_websocket(ws_listener).await.unwrap();
     });
 
     task::block_on(async {
@@ -46,27 +164,259 @@ pub fn run(tcp_host: String, ws_host: String) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Disconnect all clients and exit the process.
+pub fn shutdown() -> ! {
+    info!("Shutting down the server");
+
+    metrics::set_ready(false);
+
+    let clients: Vec<Client> = CLIENTS.lock().unwrap().values().cloned().collect();
+    for client in clients {
+        let _ = client.send("Server is shutting down");
+
+        end_session(client.id);
+        let _ = client.close();
+    }
+
+    // give the clients time to receive the message
+    thread::sleep(SHUTDOWN_GRACE);
+
+    #[cfg(unix)]
+    if let Some(path) = CONFIG.read().unwrap().unix_socket.as_ref() {
+        let _ = std::fs::remove_file(path);
+    }
+
+    std::process::exit(0)
+}
+
+/// Periodically disconnect clients that haven't sent any message for the timeout
+fn disconnect_idle_clients(timeout: Duration) {
+    loop {
+        thread::sleep(timeout.min(IDLE_CHECK_INTERVAL));
+
+        let clients: Vec<Client> = CLIENTS.lock().unwrap().values().cloned().collect();
+
+        for client in clients {
+            if client.metadata.idle_for() < timeout {
+                continue;
+            }
+
+            info!("Disconnecting idle client {}", client.id);
+
+            end_session(client.id);
+
+            // the client could disconnect in the meantime
+            let _ = client.send("Disconnected due to inactivity");
+            let _ = client.close();
+        }
+    }
+}
+
+/// Handle the client connection until it's closed
+fn handle_connection(mut client: Client, span: Span) {
+    // the address reported by the proxy is known only after accepting the connection
+    if let Some(addr) = client.proxied_addr {
+        if moderation::is_addr_banned(addr.ip()) {
+            info!("Rejected connection from banned address {}", addr);
+            metrics::record_rejected("banned");
+            let _ = client.close();
+            return;
+        }
+    }
+
+    metrics::record_connection(client.metadata.transport);
+
+    // the nickname is recorded on the span after it's set
+    client.span = span.clone();
+
+    // insert the cloned client to CLIENTS
+    CLIENTS.lock().unwrap().insert(client.id, client.clone());
+
+    // add span to logger
+    let _enter = span.enter();
+
+    if let Err(err) = task::block_on(process(&mut client)) {
+        let err = err.to_string();
+
+        // client disconnect e.g. using ctrl + c
+        if err == "disconnected" || err.contains("Connection reset") {
+            info!("Client disconnected")
+        } else {
+            error!("{}", err);
+        }
+    }
+
+    // the id could be changed by resuming a session
+    CLIENTS.lock().unwrap().remove(&client.id);
+
+    // stop the writer thread
+    let _ = client.close();
+
+    // delete the client from rooms, unless its session can be resumed
+    if !detach_session(&client) {
+        end_session(client.id);
+        rooms::leave_all(client.id);
+    }
+}
+
+/// Resume the detached session, the client takes over the id and state of the
+/// disconnected client.
+fn resume(client: &mut Client, token: &str) -> anyhow::Result<()> {
+    let Some((previous, addr, queue)) = sessions::resume_session(token) else {
+        return client.send("invalid or expired session token");
+    };
+
+    // the account or the address could be banned while the session was detached
+    let banned = addr.is_some_and(moderation::is_addr_banned)
+        || previous
+            .account
+            .lock()
+            .unwrap()
+            .as_deref()
+            .is_some_and(moderation::is_account_banned);
+
+    if banned {
+        end_session(previous.id);
+        rooms::leave_all(previous.id);
+
+        return client.send("invalid or expired session token");
+    }
+
+    // forget the state of this connection
+    CLIENTS.lock().unwrap().remove(&client.id);
+    end_session(client.id);
+    rooms::leave_all(client.id);
+
+    client.id = previous.id;
+    client.map = previous.map;
+    client.extensions = previous.extensions;
+    client.roles = previous.roles;
+    client.account = previous.account;
+    client.nick = previous.nick;
+    client.muted_until = previous.muted_until;
+
+    {
+        let mut clients = CLIENTS.lock().unwrap();
+
+        // the nickname could be taken while the client was disconnected
+        let nick = client.nick.lock().unwrap().clone();
+        if let Some(nick) = nick {
+            if clients.values().any(|other| {
+                other
+                    .nick
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .is_some_and(|other| other.eq_ignore_ascii_case(&nick))
+            }) {
+                *client.nick.lock().unwrap() = None;
+            }
+        }
+
+        clients.insert(client.id, client.clone());
+    }
+
+    if let Some(nick) = &*client.nick.lock().unwrap() {
+        client.span.record("nick", nick.as_str());
+    }
+
+    client.send(format!("Session resumed as client {}", client.id))?;
+
+    if client.nick.lock().unwrap().is_none() {
+        client.send("Your nickname was taken while you were disconnected")?;
+    }
+
+    for msg in queue {
+        client.send(msg)?;
+    }
+
+    Ok(())
+}
+
 /// Process client connection
-async fn process(client: Client) -> anyhow::Result<()> {
-    let client_addr = client.peer_addr()?;
+async fn process(client: &mut Client) -> anyhow::Result<()> {
+    match client.metadata.peer_credentials {
+        Some(credentials) => info!("Processing unix socket connection: {}", credentials),
+        None if client.proxied_addr.is_some() => info!(
+            "Processing client connection: {} (via proxy {})",
+            client.peer_addr()?,
+            client.socket_addr()?
+        ),
+        None => info!("Processing client connection: {}", client.peer_addr()?),
+    }
+
+    if let Some(handshake) = &client.metadata.handshake {
+        info!(
+            "WebSocket path: {}, subprotocol: {}",
+            handshake.path,
+            client.protocol()
+        );
+    }
 
-    info!("Processing client connection: {}", client_addr);
+    // give the client roles from the permissions policy
+    permissions::assign_initial_roles(client);
+
+    // authenticate the client using credentials from the WebSocket handshake
+    let credentials = client.handshake_credentials.lock().unwrap().take();
+    if let Some(credentials) = credentials {
+        if !auth::authenticate(client, &credentials).await? {
+            client.send("authentication failed")?;
+        }
+    }
 
     // run `onConnect` events
     client
         .run_events(EventType::OnConnect, EventData::None)
         .await?;
 
+    if let Some(token) = new_session(client) {
+        client.send(format!("Session token: {token}"))?;
+    }
+
+    // join the room of the WebSocket path
+    if let Some(room) = routes::client_route(client).and_then(|route| route.room) {
+        if let Err(err) = rooms::join(client, &room, None) {
+            client.send(format!("Failed to join room `{room}`: {err}"))?;
+        }
+    }
+
     loop {
-        let buf = client.read()?;
+        let msg = client.read_message()?;
+
+        // resuming a session changes the id of the client, so it isn't a command
+        if let Some(token) = msg.as_text().and_then(|buf| buf.strip_prefix("/resume ")) {
+            resume(client, token.trim())?;
+            continue;
+        }
 
         // functions for error handling see `if` below function
-        async fn handle(client: &Client, buf: String) -> anyhow::Result<()> {
+        async fn handle(client: &Client, msg: Message) -> anyhow::Result<()> {
             // run `onSend` events
             client
-                .run_events(EventType::OnSend, EventData::None)
+                .run_events(EventType::OnSend, EventData::Message(msg.clone()))
                 .await?;
 
+            let buf = match msg {
+                Message::Text(buf) => buf,
+                // binary messages are handled only by plugins
+                msg => {
+                    let handled = client
+                        .plugins_manager()
+                        .events
+                        .iter()
+                        .any(|event| event.event() == EventType::OnBinaryMessage);
+
+                    if !handled {
+                        client.send("binary messages are not supported")?;
+                        return Ok(());
+                    }
+
+                    return client
+                        .run_events(EventType::OnBinaryMessage, EventData::Message(msg))
+                        .await;
+                },
+            };
+
             let mut args: Vec<&str> = buf.split_ascii_whitespace().collect();
 
             // if client sent an empty buffer
@@ -81,16 +431,43 @@ async fn process(client: Client) -> anyhow::Result<()> {
             args = args[1..args.len()].to_vec();
 
             // find command
-            let command = client
-                .plugins_manager
+            let plugins_manager = client.plugins_manager();
+            let command = plugins_manager
                 .commands
                 .iter()
                 .enumerate()
                 .find(|&(_i, command)| command.name() == cmd || command.aliases().contains(&cmd));
 
+            // commands can be limited by the route of the WebSocket path
+            let command = command.filter(|(_i, cmd)| {
+                routes::client_route(client).is_none_or(|route| route.allows_command(cmd.name()))
+            });
+
             // execute command, if command isn't blocked
             // to block a command return error in the `onCommand` event
             if let Some((_i, cmd)) = command {
+                // check if the client is authenticated
+                if CONFIG.read().unwrap().require_auth
+                    && cmd.requires_auth()
+                    && !client.is_authenticated()
+                {
+                    client.send("authentication required")?;
+                    return Ok(());
+                }
+
+                // check if the client has permission to execute the command
+                let permission = POLICY
+                    .read()
+                    .unwrap()
+                    .command_permission(cmd.name(), cmd.required_permission());
+
+                if let Some(permission) = permission {
+                    if !client.has_permission(&permission) {
+                        client.send("permission denied")?;
+                        return Ok(());
+                    }
+                }
+
                 // run `onCommand` events
                 if client
                     .run_events(
@@ -100,8 +477,14 @@ async fn process(client: Client) -> anyhow::Result<()> {
                     .await
                     .is_ok()
                 {
+                    client.metadata.record_command();
+
                     // execute command
-                    cmd.execute(client, args).await?;
+                    let started = Instant::now();
+                    let result = cmd.execute(client, args).await;
+                    metrics::record_command(cmd.name(), started.elapsed());
+
+                    result?;
                 }
             } else {
                 client.send("unknown command")?;
@@ -111,11 +494,13 @@ async fn process(client: Client) -> anyhow::Result<()> {
         }
 
         // handle errors from message processing
-        if let Err(err) = handle(&client, buf).await {
+        if let Err(err) = handle(client, msg).await {
+            client.metadata.record_error();
+
             let err = err.to_string();
 
-            // client disconnect e.g. using ctrl + c
-            if err.contains("Broken pipe") {
+            // client disconnect e.g. using ctrl + c or too slow client
+            if err == "disconnected" || err == "send queue full" {
                 return Err(anyhow!("disconnected"));
             } else {
                 error!("Unexpected error in message handler: {}", err);
@@ -127,14 +512,48 @@ async fn process(client: Client) -> anyhow::Result<()> {
     }
 }
 
-async fn start_tcp(host: String) -> anyhow::Result<()> {
-    let listener = TcpListener::bind(host)?;
+/// Returns `true` if the connection comes from a banned address.
+fn is_banned(stream: &TcpStream) -> bool {
+    match stream.peer_addr() {
+        Ok(addr) if moderation::is_addr_banned(addr.ip()) => {
+            info!("Rejected connection from banned address {}", addr);
+            metrics::record_rejected("banned");
+            true
+        },
+        _ => false,
+    }
+}
+
+/// Read the PROXY protocol header if it's enabled and the connection comes from
+/// a trusted proxy. Returns the address of the client reported by the proxy.
+fn read_proxy(stream: &TcpStream) -> anyhow::Result<Option<SocketAddr>> {
+    let addr = stream.peer_addr()?;
+
+    {
+        let config = CONFIG.read().unwrap();
 
+        if !config.proxy_protocol || !config.is_trusted_proxy(addr.ip()) {
+            return Ok(None);
+        }
+    }
+
+    read_proxy_header(stream).map_err(|err| {
+        metrics::record_rejected("proxy");
+
+        anyhow!("failed to read PROXY protocol header from {addr}: {err}")
+    })
+}
+
+async fn start_tcp(listener: TcpListener) -> anyhow::Result<()> {
     let incoming = listener.incoming();
 
     for stream in incoming {
         let stream = stream?;
 
+        if is_banned(&stream) {
+            continue;
+        }
+
         // get id for the client
         let id = *CLIENT_NEXT.lock().unwrap();
 
@@ -142,43 +561,33 @@ async fn start_tcp(host: String) -> anyhow::Result<()> {
         *CLIENT_NEXT.lock().unwrap() += 1;
 
         thread::spawn(move || {
-            // get id for the client and add one to next id
-            let client = Client::new_tcp(stream, id);
-
-            // insert the cloned client to CLIENTS
-            CLIENTS.lock().unwrap().insert(id, client.clone());
-
-            // add span to logger
-            let span = span!(Level::ERROR, "TCP", id = client.id);
-            let _enter = span.enter();
-
-            if let Err(err) = task::block_on(process(client)) {
-                let err = err.to_string();
-
-                // client disconnect e.g. using ctrl + c
-                if err == "disconnected" {
-                    info!("Client disconnected")
-                } else {
+            let proxied_addr = match read_proxy(&stream) {
+                Ok(addr) => addr,
+                Err(err) => {
                     error!("{}", err);
-                }
-            }
+                    return;
+                },
+            };
+
+            let client = Client::new_tcp_proxied(stream, id, proxied_addr);
 
-            // delete the client from CLIENTS map
-            CLIENTS.lock().unwrap().remove(&id);
+            handle_connection(client, span!(Level::ERROR, "TCP", id, nick = field::Empty));
         });
     }
 
     Ok(())
 }
 
-async fn start_websocket(host: String) -> anyhow::Result<()> {
-    let listener = TcpListener::bind(host)?;
-
+async fn start_websocket(listener: TcpListener) -> anyhow::Result<()> {
     let incoming = listener.incoming();
 
     for stream in incoming {
         let stream = stream?;
 
+        if is_banned(&stream) {
+            continue;
+        }
+
         // get id for the client
         let id = *CLIENT_NEXT.lock().unwrap();
 
@@ -186,32 +595,182 @@ async fn start_websocket(host: String) -> anyhow::Result<()> {
         *CLIENT_NEXT.lock().unwrap() += 1;
 
         thread::spawn(move || {
-            let client = Client::new_websocket(stream, id).unwrap();
+            let client = match read_proxy(&stream).and_then(|addr| {
+                let deflate = CONFIG.read().unwrap().ws_deflate;
 
-            // insert the cloned client to CLIENTS
-            CLIENTS.lock().unwrap().insert(id, client.clone());
+                Client::new_websocket_proxied(stream, id, addr, deflate)
+            }) {
+                Ok(client) => client,
+                Err(err) => {
+                    error!("{}", err);
+                    return;
+                },
+            };
 
-            // add span to logger
-            let span = span!(Level::ERROR, "WS", id = client.id);
-            let _enter = span.enter();
+            handle_connection(client, span!(Level::ERROR, "WS", id, nick = field::Empty));
+        });
+    }
 
-            if let Err(err) = task::block_on(process(client)) {
-                let err = err.to_string();
+    Ok(())
+}
 
-                // client disconnect e.g. using ctrl + c
-                if err == "disconnected"
-                    || err.contains("Connection reset without closing handshake")
-                {
-                    info!("Client disconnected")
-                } else {
+fn start_single_port(listener: TcpListener) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                error!("Failed to accept connection on the single port: {}", err);
+                continue;
+            },
+        };
+
+        if is_banned(&stream) {
+            continue;
+        }
+
+        // get id for the client
+        let id = *CLIENT_NEXT.lock().unwrap();
+
+        // add one to next id
+        *CLIENT_NEXT.lock().unwrap() += 1;
+
+        thread::spawn(move || {
+            // the PROXY protocol header is sent before the data of the client
+            let proxied_addr = match read_proxy(&stream) {
+                Ok(addr) => addr,
+                Err(err) => {
                     error!("{}", err);
-                }
-            }
+                    return;
+                },
+            };
+
+            // sniffing waits for the first bytes, so it's done outside of the accept loop
+            let client = match sniff_protocol(&stream) {
+                Ok(SniffedProtocol::TCP) => Client::new_tcp_proxied(stream, id, proxied_addr),
+                Ok(SniffedProtocol::WebSocket) => {
+                    let deflate = CONFIG.read().unwrap().single_port_deflate;
+
+                    match Client::new_websocket_proxied(stream, id, proxied_addr, deflate) {
+                        Ok(client) => client,
+                        Err(err) => {
+                            error!("{}", err);
+                            return;
+                        },
+                    }
+                },
+                Ok(SniffedProtocol::TLS) => {
+                    if let Ok(addr) = stream.peer_addr() {
+                        info!("Rejected TLS connection from {addr}, TLS is not supported");
+                    }
+                    metrics::record_rejected("tls");
+                    return;
+                },
+                Err(err) => {
+                    info!(
+                        "Connection closed before the protocol was detected: {}",
+                        err
+                    );
+                    return;
+                },
+            };
+
+            let span = match client.metadata.transport {
+                Transport::WebSocket => span!(Level::ERROR, "WS", id, nick = field::Empty),
+                _ => span!(Level::ERROR, "TCP", id, nick = field::Empty),
+            };
+
+            handle_connection(client, span);
+        });
+    }
+}
+
+fn start_udp(socket: UdpSocket) {
+    let socket = Arc::new(socket);
+
+    let mut buf = [0; MAX_PACKET_LEN];
+
+    loop {
+        let (len, addr) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(err) => {
+                error!("Failed to receive UDP datagram: {}", err);
+                continue;
+            },
+        };
+
+        if moderation::is_addr_banned(addr.ip()) || !udp::check_rate_limit(addr.ip()) {
+            continue;
+        }
+
+        // datagrams from known addresses are delivered to their clients
+        let Some(datagram) = udp::deliver_datagram(addr, buf[..len].to_vec()) else {
+            continue;
+        };
+
+        // nothing is sent to the address (possibly spoofed) until it sends a command
+        if !is_command(&datagram) {
+            debug!("Ignoring UDP datagram from {} without a session", addr);
+            continue;
+        }
+
+        let max_peers = CONFIG.read().unwrap().udp_max_peers;
+
+        let Some(peer) = udp::new_peer(&socket, addr, datagram, max_peers) else {
+            debug!("Too many UDP sessions, ignoring datagram from {}", addr);
+            continue;
+        };
 
-            // delete the client from CLIENTS map
-            CLIENTS.lock().unwrap().remove(&id);
+        // get id for the client
+        let id = *CLIENT_NEXT.lock().unwrap();
+
+        // add one to next id
+        *CLIENT_NEXT.lock().unwrap() += 1;
+
+        thread::spawn(move || {
+            let client = Client::new_udp(peer, id);
+
+            handle_connection(client, span!(Level::ERROR, "UDP", id, nick = field::Empty));
         });
     }
+}
 
-    Ok(())
+/// Returns `true` if the datagram is a registered command or `/resume`.
+fn is_command(datagram: &[u8]) -> bool {
+    let Some(name) = std::str::from_utf8(datagram)
+        .ok()
+        .and_then(|text| text.split_ascii_whitespace().next())
+    else {
+        return false;
+    };
+
+    name == "/resume"
+        || plugins_manager()
+            .commands
+            .iter()
+            .any(|cmd| cmd.name() == name || cmd.aliases().contains(&name))
+}
+
+#[cfg(unix)]
+fn start_unix(listener: UnixListener) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                error!("Failed to accept unix socket connection: {}", err);
+                continue;
+            },
+        };
+
+        // get id for the client
+        let id = *CLIENT_NEXT.lock().unwrap();
+
+        // add one to next id
+        *CLIENT_NEXT.lock().unwrap() += 1;
+
+        thread::spawn(move || {
+            let client = Client::new_unix(stream, id);
+
+            handle_connection(client, span!(Level::ERROR, "UNIX", id, nick = field::Empty));
+        });
+    }
 }
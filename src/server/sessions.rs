@@ -0,0 +1,210 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    sync::Mutex,
+    time::Instant,
+};
+
+use lazy_static::lazy_static;
+use tracing::info;
+
+use super::{Client, CONFIG};
+use crate::{auth::generate_token, rooms};
+
+/// Max number of messages queued for a detached session, older messages are dropped.
+pub const MAX_QUEUED_MESSAGES: usize = 256;
+
+lazy_static! {
+    /// Resumable sessions by their tokens
+    static ref SESSIONS: Mutex<HashMap<String, Session>> = Mutex::new(HashMap::new());
+}
+
+/// Session of the client which can be resumed after the connection is lost.
+#[derive(Debug)]
+struct Session {
+    /// ID of the client owning the session
+    client_id: usize,
+    /// IP address of the connection which started the session
+    addr: Option<IpAddr>,
+    /// State of the client whose connection was lost
+    detached: Option<Detached>,
+}
+
+#[derive(Debug)]
+struct Detached {
+    /// Client with the state from the lost connection
+    client: Client,
+    /// When the session can't be resumed anymore
+    expires_at: Instant,
+    /// Messages sent to the client after the connection was lost
+    queue: VecDeque<String>,
+}
+
+/// Issue a session token for the client. Returns `None` if sessions are disabled.
+pub fn new_session(client: &Client) -> Option<String> {
+    CONFIG.read().unwrap().session_grace?;
+
+    let token = generate_token();
+
+    SESSIONS.lock().unwrap().insert(
+        token.clone(),
+        Session {
+            client_id: client.id,
+            addr: client.peer_addr().ok().map(|addr| addr.ip()),
+            detached: None,
+        },
+    );
+
+    Some(token)
+}
+
+/// End the session of the client, so it can't be resumed (e.g. when the client is kicked).
+pub fn end_session(client_id: usize) {
+    SESSIONS
+        .lock()
+        .unwrap()
+        .retain(|_token, session| session.client_id != client_id);
+}
+
+/// Keep the state of the disconnected client until the grace period ends.
+/// Returns `false` if the client has no session to resume.
+pub(crate) fn detach_session(client: &Client) -> bool {
+    let Some(grace) = CONFIG.read().unwrap().session_grace else {
+        return false;
+    };
+
+    let mut sessions = SESSIONS.lock().unwrap();
+
+    let Some(session) = sessions
+        .values_mut()
+        .find(|session| session.client_id == client.id)
+    else {
+        return false;
+    };
+
+    session.detached = Some(Detached {
+        client: client.clone(),
+        expires_at: Instant::now() + grace,
+        queue: VecDeque::new(),
+    });
+
+    info!("Session of client {} detached", client.id);
+
+    true
+}
+
+/// Take the state of the detached session with the token, the IP address which
+/// started the session and queued messages.
+pub(crate) fn resume_session(token: &str) -> Option<(Client, Option<IpAddr>, Vec<String>)> {
+    remove_expired_sessions();
+
+    let mut sessions = SESSIONS.lock().unwrap();
+
+    let session = sessions.get_mut(token)?;
+    let detached = session.detached.take()?;
+
+    info!("Session of client {} resumed", detached.client.id);
+
+    Some((detached.client, session.addr, detached.queue.into()))
+}
+
+/// End detached sessions for which the function returns `true` (e.g. sessions
+/// of banned clients) and remove their clients from the rooms. The function
+/// gets the client of the session and the IP address which started it.
+pub(crate) fn end_detached_sessions<F>(f: F) -> usize
+where
+    F: Fn(&Client, Option<IpAddr>) -> bool,
+{
+    let mut ended = Vec::new();
+
+    SESSIONS
+        .lock()
+        .unwrap()
+        .retain(|_token, session| match &session.detached {
+            Some(detached) if f(&detached.client, session.addr) => {
+                ended.push(session.client_id);
+                false
+            },
+            _ => true,
+        });
+
+    for client_id in &ended {
+        info!("Session of client {} ended", client_id);
+
+        rooms::leave_all(*client_id);
+    }
+
+    ended.len()
+}
+
+/// Returns `true` if the client with the id lost its connection and can resume its session.
+pub fn is_session_detached(client_id: usize) -> bool {
+    SESSIONS
+        .lock()
+        .unwrap()
+        .values()
+        .any(|session| session.client_id == client_id && session.detached.is_some())
+}
+
+/// Queue the message for the detached session of the client.
+/// Returns `false` if the client has no detached session.
+pub fn queue_message(client_id: usize, msg: String) -> bool {
+    let mut sessions = SESSIONS.lock().unwrap();
+
+    let detached = sessions
+        .values_mut()
+        .filter(|session| session.client_id == client_id)
+        .find_map(|session| session.detached.as_mut());
+
+    match detached {
+        Some(detached) => {
+            push_message(&mut detached.queue, msg);
+            true
+        },
+        None => false,
+    }
+}
+
+/// Queue the message for all detached sessions.
+pub fn queue_broadcast(msg: &str) {
+    for detached in SESSIONS
+        .lock()
+        .unwrap()
+        .values_mut()
+        .filter_map(|session| session.detached.as_mut())
+    {
+        push_message(&mut detached.queue, msg.to_string());
+    }
+}
+
+/// Remove sessions whose grace period has ended and their clients from the rooms.
+pub(crate) fn remove_expired_sessions() {
+    let now = Instant::now();
+
+    let mut expired = Vec::new();
+
+    SESSIONS
+        .lock()
+        .unwrap()
+        .retain(|_token, session| match &session.detached {
+            Some(detached) if detached.expires_at <= now => {
+                expired.push(session.client_id);
+                false
+            },
+            _ => true,
+        });
+
+    for client_id in expired {
+        info!("Session of client {} expired", client_id);
+
+        rooms::leave_all(client_id);
+    }
+}
+
+fn push_message(queue: &mut VecDeque<String>, msg: String) {
+    if queue.len() >= MAX_QUEUED_MESSAGES {
+        queue.pop_front();
+    }
+
+    queue.push_back(msg);
+}
@@ -0,0 +1,81 @@
+use std::{
+    io::{self, ErrorKind},
+    net::TcpStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long to wait for the first bytes of a connection. Raw TCP clients
+/// which wait for the server to send something first are detected after it.
+pub const SNIFF_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Protocol detected from the first bytes of a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SniffedProtocol {
+    /// Raw TCP client
+    TCP,
+    /// HTTP request (WebSocket handshake)
+    WebSocket,
+    /// TLS ClientHello (TLS isn't supported)
+    TLS,
+}
+
+/// Detect the protocol of the connection by peeking its first bytes, the bytes
+/// are not consumed.
+pub fn sniff_protocol(stream: &TcpStream) -> io::Result<SniffedProtocol> {
+    let deadline = Instant::now() + SNIFF_TIMEOUT;
+
+    let mut buf = [0; 4];
+
+    stream.set_read_timeout(Some(SNIFF_TIMEOUT))?;
+
+    let protocol = loop {
+        let len = match stream.peek(&mut buf) {
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(len) => len,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                break SniffedProtocol::TCP
+            },
+            Err(err) => return Err(err),
+        };
+
+        let protocol = detect(&buf[..len]);
+
+        // wait for more bytes if the prefix is ambiguous
+        if protocol.is_some() || Instant::now() >= deadline {
+            break protocol.unwrap_or(SniffedProtocol::TCP);
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    stream.set_read_timeout(None)?;
+
+    Ok(protocol)
+}
+
+/// Detect the protocol from the bytes, returns `None` if more bytes are needed.
+fn detect(buf: &[u8]) -> Option<SniffedProtocol> {
+    const HTTP_GET: &[u8] = b"GET ";
+
+    // TLS record of type handshake (0x16) and version 3.x
+    match buf {
+        [0x16] => return None,
+        [0x16, 0x03, ..] => return Some(SniffedProtocol::TLS),
+        _ => {},
+    }
+
+    if buf.len() >= HTTP_GET.len() {
+        if buf.starts_with(HTTP_GET) {
+            return Some(SniffedProtocol::WebSocket);
+        }
+
+        return Some(SniffedProtocol::TCP);
+    }
+
+    if HTTP_GET.starts_with(buf) {
+        return None;
+    }
+
+    Some(SniffedProtocol::TCP)
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    net::{IpAddr, SocketAddr, UdpSocket},
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use tracing::{debug, info};
+
+/// Max number of datagrams waiting in the inbox of the peer, newer datagrams are dropped
+pub const MAX_INBOX_DATAGRAMS: usize = 64;
+
+/// Max number of datagrams accepted from an IP address per [RATE_LIMIT_WINDOW]
+pub const RATE_LIMIT_DATAGRAMS: u32 = 32;
+
+/// Window of the per-address rate limit
+pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Max number of IP addresses tracked by the rate limit
+const MAX_RATE_LIMITED_ADDRS: usize = 16384;
+
+lazy_static! {
+    /// Pseudo-sessions of the UDP clients by their addresses
+    static ref UDP_PEERS: Mutex<HashMap<SocketAddr, UdpPeer>> = Mutex::new(HashMap::new());
+    /// Start of the current rate limit window and the number of datagrams received
+    /// in it by the IP addresses
+    static ref RATE_LIMITS: Mutex<HashMap<IpAddr, (Instant, u32)>> = Mutex::new(HashMap::new());
+}
+
+/// Remote address of the UDP listener, each address is a pseudo-session with its own client.
+#[derive(Debug, Clone)]
+pub struct UdpPeer {
+    socket: Arc<UdpSocket>,
+    addr: SocketAddr,
+    inbox: Arc<(Mutex<Inbox>, Condvar)>,
+}
+
+/// Datagrams received from the address and not read by the client yet
+#[derive(Debug, Default)]
+struct Inbox {
+    datagrams: VecDeque<Vec<u8>>,
+    closed: bool,
+}
+
+impl UdpPeer {
+    fn new(socket: Arc<UdpSocket>, addr: SocketAddr) -> Self {
+        Self {
+            socket,
+            addr,
+            inbox: Arc::new((Mutex::new(Inbox::default()), Condvar::new())),
+        }
+    }
+
+    /// Returns the address of the peer.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Wait for the next datagram from the peer. The session expires if no
+    /// datagram is received within the timeout.
+    pub fn recv(&self, timeout: Duration) -> anyhow::Result<Vec<u8>> {
+        let (inbox, condvar) = &*self.inbox;
+
+        let (mut inbox, result) = condvar
+            .wait_timeout_while(inbox.lock().unwrap(), timeout, |inbox| {
+                inbox.datagrams.is_empty() && !inbox.closed
+            })
+            .unwrap();
+
+        if let Some(datagram) = inbox.datagrams.pop_front() {
+            return Ok(datagram);
+        }
+
+        if result.timed_out() {
+            info!("UDP session of {} expired", self.addr);
+
+            inbox.closed = true;
+        }
+
+        Err(anyhow!("disconnected"))
+    }
+
+    /// Send the datagram to the peer.
+    pub fn send(&self, buf: &[u8]) -> io::Result<()> {
+        self.socket.send_to(buf, self.addr)?;
+
+        Ok(())
+    }
+
+    /// End the session, the next datagram from the address starts a new one.
+    pub fn close(&self) {
+        let (inbox, condvar) = &*self.inbox;
+
+        inbox.lock().unwrap().closed = true;
+        condvar.notify_all();
+
+        let mut peers = UDP_PEERS.lock().unwrap();
+
+        if peers
+            .get(&self.addr)
+            .is_some_and(|peer| Arc::ptr_eq(&peer.inbox, &self.inbox))
+        {
+            peers.remove(&self.addr);
+        }
+    }
+
+    /// Returns `true` if the session has ended.
+    fn is_closed(&self) -> bool {
+        self.inbox.0.lock().unwrap().closed
+    }
+
+    /// Add the received datagram to the inbox, the datagram is dropped if the
+    /// inbox is full. Returns `false` if the session has ended.
+    fn push(&self, datagram: Vec<u8>) -> bool {
+        let (inbox, condvar) = &*self.inbox;
+        let mut inbox = inbox.lock().unwrap();
+
+        if inbox.closed {
+            return false;
+        }
+
+        if inbox.datagrams.len() >= MAX_INBOX_DATAGRAMS {
+            debug!("Inbox of UDP peer {} is full, dropping datagram", self.addr);
+
+            return true;
+        }
+
+        inbox.datagrams.push_back(datagram);
+        condvar.notify_all();
+
+        true
+    }
+}
+
+/// Returns `true` if the datagram from the IP address is within the rate limit.
+pub(crate) fn check_rate_limit(ip: IpAddr) -> bool {
+    let now = Instant::now();
+
+    let mut limits = RATE_LIMITS.lock().unwrap();
+
+    if limits.len() >= MAX_RATE_LIMITED_ADDRS && !limits.contains_key(&ip) {
+        limits.retain(|_ip, (started, _count)| now.duration_since(*started) < RATE_LIMIT_WINDOW);
+
+        if limits.len() >= MAX_RATE_LIMITED_ADDRS {
+            return false;
+        }
+    }
+
+    let (started, count) = limits.entry(ip).or_insert((now, 0));
+
+    if now.duration_since(*started) >= RATE_LIMIT_WINDOW {
+        *started = now;
+        *count = 0;
+    }
+
+    *count += 1;
+
+    *count <= RATE_LIMIT_DATAGRAMS
+}
+
+/// Deliver the datagram to the session of the address. Returns the datagram
+/// back if the address has no session.
+pub(crate) fn deliver_datagram(addr: SocketAddr, datagram: Vec<u8>) -> Option<Vec<u8>> {
+    let peers = UDP_PEERS.lock().unwrap();
+
+    match peers.get(&addr) {
+        Some(peer) if peer.push(datagram.clone()) => None,
+        _ => Some(datagram),
+    }
+}
+
+/// Start a new session of the address with the first datagram. Returns `None`
+/// if there are already `max_peers` sessions.
+pub(crate) fn new_peer(
+    socket: &Arc<UdpSocket>,
+    addr: SocketAddr,
+    datagram: Vec<u8>,
+    max_peers: usize,
+) -> Option<UdpPeer> {
+    let mut peers = UDP_PEERS.lock().unwrap();
+
+    // an ended session of the address is replaced
+    let ended = peers.get(&addr).is_some_and(|peer| peer.is_closed());
+
+    if !ended && peers.len() >= max_peers {
+        return None;
+    }
+
+    let peer = UdpPeer::new(socket.clone(), addr);
+    peer.push(datagram);
+
+    peers.insert(addr, peer.clone());
+
+    Some(peer)
+}
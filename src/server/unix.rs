@@ -0,0 +1,128 @@
+use std::{
+    ffi::OsString,
+    fs,
+    io::{self, ErrorKind},
+    os::unix::{
+        fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+        io::AsRawFd,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    process,
+};
+
+use anyhow::anyhow;
+use tracing::info;
+
+use super::PeerCredentials;
+
+/// Bind the Unix domain socket and set its permissions. A stale socket file
+/// left by a previous server is removed, but a socket in use is not.
+///
+/// The socket is bound in a private directory and moved to the path after its
+/// permissions are set, so clients can't connect before.
+pub fn bind_unix<P>(path: P, mode: u32) -> anyhow::Result<UnixListener>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(anyhow!("`{}` exists and isn't a socket", path.display()));
+        }
+
+        match UnixStream::connect(path) {
+            Ok(_) => return Err(anyhow!("socket `{}` is already in use", path.display())),
+            Err(err) if err.kind() == ErrorKind::ConnectionRefused => {
+                info!("Removing stale socket `{}`", path.display());
+
+                fs::remove_file(path)?;
+            },
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid socket path `{}`", path.display()))?;
+
+    let mut dir_name = OsString::from(".");
+    dir_name.push(file_name);
+    dir_name.push(format!(".{}.tmp", process::id()));
+
+    let dir = path.with_file_name(dir_name);
+
+    fs::DirBuilder::new().mode(0o700).create(&dir)?;
+
+    let bind = || -> anyhow::Result<UnixListener> {
+        let tmp_path = dir.join("socket");
+
+        let listener = UnixListener::bind(&tmp_path)?;
+
+        fs::set_permissions(&tmp_path, fs::Permissions::from_mode(mode))?;
+        fs::rename(&tmp_path, path)?;
+
+        Ok(listener)
+    };
+
+    let result = bind();
+
+    // the socket file was moved out of the directory (or binding failed)
+    let _ = fs::remove_file(dir.join("socket"));
+    let _ = fs::remove_dir(&dir);
+
+    result
+}
+
+/// Returns credentials of the process connected to the socket.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn peer_credentials(stream: &UnixStream) -> io::Result<PeerCredentials> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    // SAFETY: `cred` and `len` are valid for writes and `len` is the size of `cred`
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(PeerCredentials {
+        uid: cred.uid,
+        gid: cred.gid,
+        pid: Some(cred.pid),
+    })
+}
+
+/// Returns credentials of the process connected to the socket.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn peer_credentials(stream: &UnixStream) -> io::Result<PeerCredentials> {
+    let mut uid = 0;
+    let mut gid = 0;
+
+    // SAFETY: `uid` and `gid` are valid for writes
+    let ret = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(PeerCredentials {
+        uid,
+        gid,
+        pid: None,
+    })
+}
@@ -0,0 +1,422 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use tungstenite::{
+    accept_hdr,
+    handshake::server::{Callback, ErrorResponse, Request, Response},
+    http::HeaderValue,
+    protocol::frame::{
+        coding::{CloseCode, Control, Data, OpCode},
+        CloseFrame, Frame, FrameSocket,
+    },
+};
+
+use super::{DeflateConfig, DeflateParams, MessageCompressor, MessageDecompressor};
+
+/// Max size of a single WebSocket frame
+pub const MAX_FRAME_LEN: usize = 16 << 20;
+
+/// Max size of a WebSocket message (after joining the fragments)
+pub const MAX_MESSAGE_LEN: usize = 64 << 20;
+
+/// Max size of the payload of a control frame (RFC 6455, section 5.5)
+const MAX_CONTROL_FRAME_LEN: usize = 125;
+
+/// Subprotocols supported by the server, in the order of preference
+pub const SUPPORTED_PROTOCOLS: [WebSocketProtocol; 2] =
+    [WebSocketProtocol::Text, WebSocketProtocol::Json];
+
+/// Subprotocol of the WebSocket connection negotiated using the
+/// `Sec-WebSocket-Protocol` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebSocketProtocol {
+    /// Messages are plain text (the default)
+    Text,
+    /// Text messages are JSON objects, e.g. `{"message": "/nick alice"}`
+    Json,
+}
+
+/// Text message of the JSON subprotocol
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonMessage {
+    message: String,
+}
+
+impl WebSocketProtocol {
+    /// Returns the name of the subprotocol.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+
+    /// Choose the subprotocol from the comma separated list requested by the client.
+    /// The first protocol requested by the client which the server supports is used.
+    pub fn negotiate(requested: &str) -> Option<Self> {
+        requested
+            .split(',')
+            .filter_map(|protocol| protocol.trim().parse().ok())
+            .find(|protocol| SUPPORTED_PROTOCOLS.contains(protocol))
+    }
+
+    /// Encode the text message sent to the client.
+    pub fn encode(&self, text: String) -> String {
+        match self {
+            Self::Text => text,
+            Self::Json => serde_json::to_string(&JsonMessage { message: text })
+                .expect("failed to serialize message"),
+        }
+    }
+
+    /// Decode the text message received from the client.
+    pub fn decode(&self, text: String) -> anyhow::Result<String> {
+        match self {
+            Self::Text => Ok(text),
+            Self::Json => serde_json::from_str::<JsonMessage>(&text)
+                .map(|msg| msg.message)
+                .map_err(|err| anyhow!("invalid JSON message: {err}")),
+        }
+    }
+}
+
+impl FromStr for WebSocketProtocol {
+    type Err = anyhow::Error;
+
+    fn from_str(protocol: &str) -> anyhow::Result<Self> {
+        match protocol {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!("unknown subprotocol `{protocol}`")),
+        }
+    }
+}
+
+impl fmt::Display for WebSocketProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// WebSocket connection split into independent halves, so a pending read
+/// doesn't block writing to the client.
+#[derive(Debug, Clone)]
+pub struct WebSocketStream {
+    reader: Arc<Mutex<WebSocketReader>>,
+    writer: WebSocketWriter,
+    /// Parameters of the negotiated permessage-deflate extension
+    deflate: Option<DeflateParams>,
+}
+
+/// Reading half of the WebSocket connection.
+#[derive(Debug)]
+struct WebSocketReader {
+    socket: FrameSocket<TcpStream>,
+    /// Opcode, compression flag and payload of the fragmented message being received
+    fragments: Option<(Data, bool, Vec<u8>)>,
+    /// Decompressor of the messages (if permessage-deflate was negotiated)
+    decompressor: Option<MessageDecompressor>,
+}
+
+/// Writing half of the WebSocket connection.
+#[derive(Debug, Clone)]
+pub struct WebSocketWriter {
+    stream: Arc<Mutex<TcpStream>>,
+    /// Close frame was sent, no more frames can be written
+    close_sent: Arc<AtomicBool>,
+    /// Compressor of the messages (if permessage-deflate was negotiated)
+    compressor: Option<Arc<Mutex<MessageCompressor>>>,
+}
+
+/// Stream used for the handshake, reads one byte at a time so no bytes after
+/// the handshake request are consumed.
+struct HandshakeStream(TcpStream);
+
+impl Read for HandshakeStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(1);
+
+        self.0.read(&mut buf[..len])
+    }
+}
+
+impl Write for HandshakeStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl WebSocketStream {
+    /// Accept the WebSocket handshake on the stream, the callback can inspect
+    /// the request and modify the response. The permessage-deflate extension
+    /// is negotiated if `deflate` is set.
+    pub fn accept<C>(
+        stream: TcpStream,
+        callback: C,
+        deflate: Option<DeflateConfig>,
+    ) -> anyhow::Result<Self>
+    where
+        C: Callback,
+    {
+        let handshake_stream = HandshakeStream(stream.try_clone()?);
+
+        let params = Arc::new(Mutex::new(None));
+
+        let negotiated = params.clone();
+        // the error type of the callback is defined by tungstenite
+        #[allow(clippy::result_large_err)]
+        let callback = move |req: &Request, res: Response| -> Result<Response, ErrorResponse> {
+            let mut res = callback.on_request(req, res)?;
+
+            let offers: Vec<&str> = req
+                .headers()
+                .get_all("Sec-WebSocket-Extensions")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .collect();
+
+            if let Some(params) = deflate.and_then(|config| config.negotiate(&offers.join(","))) {
+                // the formatted parameters are always a valid header value
+                res.headers_mut().insert(
+                    "Sec-WebSocket-Extensions",
+                    HeaderValue::from_str(&params.to_string()).unwrap(),
+                );
+
+                *negotiated.lock().unwrap() = Some(params);
+            }
+
+            Ok(res)
+        };
+
+        // the WebSocket from the handshake is dropped, frames are handled by the halves
+        accept_hdr(handshake_stream, callback)
+            .map_err(|err| anyhow!("WebSocket handshake failed: {err}"))?;
+
+        let deflate = params.lock().unwrap().take();
+
+        let reader = WebSocketReader {
+            socket: FrameSocket::new(stream.try_clone()?),
+            fragments: None,
+            decompressor: deflate.as_ref().map(MessageDecompressor::new),
+        };
+
+        let writer = WebSocketWriter {
+            stream: Arc::new(Mutex::new(stream)),
+            close_sent: Arc::new(AtomicBool::new(false)),
+            compressor: deflate
+                .as_ref()
+                .map(|params| Arc::new(Mutex::new(MessageCompressor::new(params)))),
+        };
+
+        Ok(Self {
+            reader: Arc::new(Mutex::new(reader)),
+            writer,
+            deflate,
+        })
+    }
+
+    /// Returns the parameters of the permessage-deflate extension, if it was negotiated.
+    pub fn deflate(&self) -> Option<&DeflateParams> {
+        self.deflate.as_ref()
+    }
+
+    /// Returns the writing half of the connection.
+    pub fn writer(&self) -> &WebSocketWriter {
+        &self.writer
+    }
+
+    /// Read the next data message, control frames are handled internally.
+    /// Only one thread should read from the stream at a time.
+    pub fn read_message(&self) -> anyhow::Result<(Data, Vec<u8>)> {
+        let mut reader = self.reader.lock().unwrap();
+
+        loop {
+            let frame = reader
+                .socket
+                .read_frame(Some(MAX_FRAME_LEN))?
+                .ok_or_else(|| anyhow!("disconnected"))?;
+
+            let (header, mut payload) = (frame.header().clone(), frame.into_data());
+
+            // frames from clients must be masked
+            let mask = header
+                .mask
+                .ok_or_else(|| anyhow!("received unmasked frame"))?;
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+
+            // RSV1 marks compressed messages, if permessage-deflate was negotiated
+            if header.rsv2 || header.rsv3 || header.rsv1 && reader.decompressor.is_none() {
+                return Err(anyhow!("reserved bits are not supported"));
+            }
+
+            if header.rsv1 && !matches!(header.opcode, OpCode::Data(Data::Text | Data::Binary)) {
+                return Err(anyhow!(
+                    "RSV1 is allowed only on the first frame of a message"
+                ));
+            }
+
+            // control frames can't be fragmented and have short payloads,
+            // the close frame has either no payload or a status code
+            if let OpCode::Control(control) = header.opcode {
+                if !header.is_final {
+                    return Err(self.protocol_error("received fragmented control frame"));
+                }
+
+                if payload.len() > MAX_CONTROL_FRAME_LEN {
+                    return Err(self.protocol_error("control frame is too long"));
+                }
+
+                if control == Control::Close && payload.len() == 1 {
+                    return Err(self.protocol_error("received invalid close frame"));
+                }
+            }
+
+            match header.opcode {
+                OpCode::Control(Control::Ping) => self.writer.write_frame(Frame::pong(payload))?,
+                OpCode::Control(Control::Pong) => {},
+                OpCode::Control(Control::Close) => {
+                    // reply with the same status code, codes which can't be sent
+                    // (e.g. 1005 and 1006) are replied with 1000
+                    let code = payload
+                        .get(..2)
+                        .map(|code| CloseCode::from(u16::from_be_bytes([code[0], code[1]])))
+                        .map(|code| {
+                            if code.is_allowed() {
+                                code
+                            } else {
+                                CloseCode::Normal
+                            }
+                        });
+
+                    self.writer.close(code)?;
+
+                    return Err(anyhow!("disconnected"));
+                },
+                OpCode::Control(Control::Reserved(_)) => {
+                    return Err(anyhow!("received unknown control frame"))
+                },
+                OpCode::Data(Data::Continue) => {
+                    let (_opcode, _compressed, data) = reader
+                        .fragments
+                        .as_mut()
+                        .ok_or_else(|| anyhow!("received continuation frame without a message"))?;
+
+                    if data.len() + payload.len() > MAX_MESSAGE_LEN {
+                        return Err(anyhow!("message is too long"));
+                    }
+
+                    data.extend(payload);
+
+                    if header.is_final {
+                        let (opcode, compressed, data) = reader.fragments.take().unwrap();
+
+                        return Ok((opcode, reader.decode(compressed, data)?));
+                    }
+                },
+                OpCode::Data(Data::Reserved(_)) => {
+                    return Err(anyhow!("received unknown data frame"))
+                },
+                OpCode::Data(opcode) => {
+                    if reader.fragments.is_some() {
+                        return Err(anyhow!(
+                            "received new message before the previous one ended"
+                        ));
+                    }
+
+                    if header.is_final {
+                        return Ok((opcode, reader.decode(header.rsv1, payload)?));
+                    }
+
+                    reader.fragments = Some((opcode, header.rsv1, payload));
+                },
+            }
+        }
+    }
+
+    /// Close the connection with the protocol error status code and returns the error.
+    fn protocol_error(&self, msg: &'static str) -> anyhow::Error {
+        let _ = self.writer.close(Some(CloseCode::Protocol));
+
+        anyhow!(msg)
+    }
+}
+
+impl WebSocketReader {
+    /// Decompress the payload of the message, if it's compressed.
+    fn decode(&mut self, compressed: bool, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        match &mut self.decompressor {
+            Some(decompressor) if compressed => decompressor.decompress(&data, MAX_MESSAGE_LEN),
+            _ => Ok(data),
+        }
+    }
+}
+
+impl WebSocketWriter {
+    /// Write the frame to the client. Frames are written whole, so writes from
+    /// multiple threads don't interleave.
+    pub fn write_frame(&self, frame: Frame) -> anyhow::Result<()> {
+        if self.close_sent.load(Ordering::SeqCst) {
+            return Err(anyhow!("disconnected"));
+        }
+
+        let mut buf = Vec::with_capacity(frame.len());
+        frame.format(&mut buf)?;
+
+        self.stream.lock().unwrap().write_all(&buf)?;
+
+        Ok(())
+    }
+
+    /// Write a data message to the client, the message is compressed if
+    /// permessage-deflate was negotiated.
+    pub fn write_message(&self, opcode: Data, data: Vec<u8>) -> anyhow::Result<()> {
+        let Some(compressor) = &self.compressor else {
+            return self.write_frame(Frame::message(data, OpCode::Data(opcode), true));
+        };
+
+        // messages must be written in the order they were compressed
+        let mut compressor = compressor.lock().unwrap();
+
+        let mut frame = Frame::message(compressor.compress(&data)?, OpCode::Data(opcode), true);
+        frame.header_mut().rsv1 = true;
+
+        self.write_frame(frame)
+    }
+
+    /// Send the close frame (only once) and shut down writing to the connection.
+    pub fn close(&self, code: Option<CloseCode>) -> anyhow::Result<()> {
+        let frame = Frame::close(code.map(|code| CloseFrame {
+            code,
+            reason: "".into(),
+        }));
+
+        let mut buf = Vec::with_capacity(frame.len());
+        frame.format(&mut buf)?;
+
+        let stream = self.stream.lock().unwrap();
+
+        if !self.close_sent.swap(true, Ordering::SeqCst) {
+            // the client could disconnect in the meantime
+            let _ = (&*stream).write_all(&buf);
+            let _ = stream.shutdown(Shutdown::Write);
+        }
+
+        Ok(())
+    }
+}
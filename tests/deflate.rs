@@ -0,0 +1,311 @@
+//! permessage-deflate tests, using the examples from RFC 7692 (section 7.2.3)
+//! and the soketto client as the reference implementation.
+
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use async_std::task;
+use servers::server::{
+    DeflateConfig, DeflateParams, MessageCompressor, MessageDecompressor, WebSocketStream,
+    MAX_MESSAGE_LEN,
+};
+use soketto::{
+    connection::Mode,
+    extension::deflate::Deflate,
+    handshake::{self, ServerResponse},
+};
+use tungstenite::{
+    handshake::server::{Request, Response},
+    protocol::frame::coding::Data,
+};
+
+/// "Hello" compressed with an empty LZ77 window
+const HELLO: [u8; 7] = [0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00];
+
+/// "Hello" compressed after the previous "Hello" (sharing the LZ77 window)
+const HELLO_SHARED: [u8; 5] = [0xf2, 0x00, 0x11, 0x00, 0x00];
+
+const PARAMS: DeflateParams = DeflateParams {
+    server_no_context_takeover: false,
+    client_no_context_takeover: false,
+    server_max_window_bits: 15,
+    client_max_window_bits: None,
+};
+
+#[test]
+fn rfc7692_decompress() {
+    let mut decompressor = MessageDecompressor::new(&PARAMS);
+
+    // a message compressed using one deflate block
+    assert_eq!(
+        decompressor.decompress(&HELLO, MAX_MESSAGE_LEN).unwrap(),
+        b"Hello"
+    );
+
+    // the same message sharing the LZ77 window with the previous one
+    assert_eq!(
+        decompressor
+            .decompress(&HELLO_SHARED, MAX_MESSAGE_LEN)
+            .unwrap(),
+        b"Hello"
+    );
+
+    // deflate block with no compression
+    let stored = [
+        0x00, 0x05, 0x00, 0xfa, 0xff, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x00,
+    ];
+    assert_eq!(
+        MessageDecompressor::new(&PARAMS)
+            .decompress(&stored, MAX_MESSAGE_LEN)
+            .unwrap(),
+        b"Hello"
+    );
+
+    // deflate block with BFINAL set
+    let bfinal = [0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00, 0x00];
+    assert_eq!(
+        MessageDecompressor::new(&PARAMS)
+            .decompress(&bfinal, MAX_MESSAGE_LEN)
+            .unwrap(),
+        b"Hello"
+    );
+
+    // two deflate blocks in one message
+    let two_blocks = [
+        0xf2, 0x48, 0x05, 0x00, 0x00, 0x00, 0xff, 0xff, 0xca, 0xc9, 0xc9, 0x07, 0x00,
+    ];
+    assert_eq!(
+        MessageDecompressor::new(&PARAMS)
+            .decompress(&two_blocks, MAX_MESSAGE_LEN)
+            .unwrap(),
+        b"Hello"
+    );
+}
+
+#[test]
+fn rfc7692_compress() {
+    let mut compressor = MessageCompressor::new(&PARAMS);
+
+    assert_eq!(compressor.compress(b"Hello").unwrap(), HELLO);
+    assert_eq!(compressor.compress(b"Hello").unwrap(), HELLO_SHARED);
+
+    // without context takeover every message is compressed with an empty window
+    let mut compressor = MessageCompressor::new(&DeflateParams {
+        server_no_context_takeover: true,
+        ..PARAMS
+    });
+
+    assert_eq!(compressor.compress(b"Hello").unwrap(), HELLO);
+    assert_eq!(compressor.compress(b"Hello").unwrap(), HELLO);
+}
+
+#[test]
+fn decompress_limit() {
+    let data = vec![b'a'; 1 << 20];
+
+    let compressed = MessageCompressor::new(&PARAMS).compress(&data).unwrap();
+
+    assert!(MessageDecompressor::new(&PARAMS)
+        .decompress(&compressed, 1 << 10)
+        .is_err());
+}
+
+#[test]
+fn negotiate() {
+    let config = DeflateConfig::default();
+
+    let params = config.negotiate("permessage-deflate").unwrap();
+    assert_eq!(params, PARAMS);
+    assert_eq!(params.to_string(), "permessage-deflate");
+
+    // window bits of the client are limited only if it supports it
+    let params = "client_max_window_bits=10; server_no_context_takeover"
+        .parse::<DeflateConfig>()
+        .unwrap()
+        .negotiate("permessage-deflate; client_max_window_bits")
+        .unwrap();
+    assert_eq!(
+        params.to_string(),
+        "permessage-deflate; server_no_context_takeover; client_max_window_bits=10"
+    );
+
+    // 8 bits window of the server isn't supported, the next offer is used
+    let params = config
+        .negotiate(
+            "permessage-deflate; server_max_window_bits=8, permessage-deflate; \
+             server_max_window_bits=12",
+        )
+        .unwrap();
+    assert_eq!(
+        params.to_string(),
+        "permessage-deflate; server_max_window_bits=12"
+    );
+
+    // offers with unknown or duplicated parameters are declined
+    assert!(config.negotiate("permessage-deflate; foo").is_none());
+    assert!(config
+        .negotiate("permessage-deflate; client_no_context_takeover; client_no_context_takeover")
+        .is_none());
+    assert!(config.negotiate("x-webkit-deflate-frame").is_none());
+
+    assert!("server_max_window_bits=8".parse::<DeflateConfig>().is_err());
+    assert!("foo".parse::<DeflateConfig>().is_err());
+}
+
+/// Accept a WebSocket connection with permessage-deflate enabled.
+fn accept(listener: TcpListener, config: DeflateConfig) -> WebSocketStream {
+    let (stream, _addr) = listener.accept().unwrap();
+
+    // the error type of the callback is defined by tungstenite
+    #[allow(clippy::result_large_err)]
+    let callback = |_req: &Request, res: Response| Ok(res);
+
+    WebSocketStream::accept(stream, callback, Some(config)).unwrap()
+}
+
+#[test]
+fn rfc7692_frames() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || accept(listener, DeflateConfig::default()));
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    stream
+        .write_all(
+            b"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+              Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\
+              Sec-WebSocket-Extensions: permessage-deflate\r\n\r\n",
+        )
+        .unwrap();
+
+    let websocket = server.join().unwrap();
+
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte).unwrap();
+        response.push(byte[0]);
+    }
+    let response = String::from_utf8(response).unwrap().to_ascii_lowercase();
+    assert!(response.contains("sec-websocket-extensions: permessage-deflate\r\n"));
+
+    // compressed text frame with RSV1 set, masked with a zero key
+    let mut frame = vec![0xc1, 0x80 | HELLO.len() as u8, 0, 0, 0, 0];
+    frame.extend(HELLO);
+    stream.write_all(&frame).unwrap();
+
+    // the same message split into two fragments
+    let mut frame = vec![0x41, 0x83, 0, 0, 0, 0];
+    frame.extend(&HELLO[..3]);
+    frame.extend([0x80, 0x84, 0, 0, 0, 0]);
+    frame.extend(&HELLO[3..]);
+    stream.write_all(&frame).unwrap();
+
+    for _ in 0..2 {
+        let (opcode, data) = websocket.read_message().unwrap();
+        assert_eq!(opcode, Data::Text);
+        assert_eq!(data, b"Hello");
+    }
+
+    // messages of the server share the LZ77 window
+    for _ in 0..2 {
+        websocket
+            .writer()
+            .write_message(Data::Text, b"Hello".to_vec())
+            .unwrap();
+    }
+
+    let mut frames = [0; 16];
+    stream.read_exact(&mut frames).unwrap();
+    assert_eq!(frames[..2], [0xc1, 0x07]);
+    assert_eq!(frames[2..9], HELLO);
+    assert_eq!(frames[9..11], [0xc1, 0x05]);
+    assert_eq!(frames[11..], HELLO_SHARED);
+}
+
+/// Send messages using the soketto client and read the echo of the server.
+fn soketto_echo(config: DeflateConfig, client_extension: Deflate, messages: &[String]) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        let websocket = accept(listener, config);
+
+        assert!(websocket.deflate().is_some());
+
+        // echo messages until the client disconnects
+        while let Ok((opcode, data)) = websocket.read_message() {
+            websocket.writer().write_message(opcode, data).unwrap();
+        }
+    });
+
+    task::block_on(async {
+        let stream = async_std::net::TcpStream::connect(addr).await.unwrap();
+
+        let mut client = handshake::Client::new(stream, "localhost", "/");
+        client.add_extension(Box::new(client_extension));
+
+        assert!(matches!(
+            client.handshake().await.unwrap(),
+            ServerResponse::Accepted { .. }
+        ));
+
+        let (mut sender, mut receiver) = client.into_builder().finish();
+
+        for msg in messages {
+            sender.send_text(msg).await.unwrap();
+            sender.flush().await.unwrap();
+
+            let mut data = Vec::new();
+            receiver.receive_data(&mut data).await.unwrap();
+
+            assert_eq!(&String::from_utf8(data).unwrap(), msg);
+        }
+
+        sender.close().await.unwrap();
+    });
+
+    server.join().unwrap();
+}
+
+/// Large JSON payloads, like the ones sent by browser clients.
+fn json_messages() -> Vec<String> {
+    (0..5)
+        .map(|i| {
+            let items: Vec<String> = (0..2000)
+                .map(|j| format!(r#"{{"id":{j},"name":"item {j}","message":{i}}}"#))
+                .collect();
+
+            format!(r#"{{"message":"[{}]"}}"#, items.join(","))
+        })
+        .collect()
+}
+
+#[test]
+fn soketto_interop() {
+    soketto_echo(
+        DeflateConfig::default(),
+        Deflate::new(Mode::Client),
+        &json_messages(),
+    );
+}
+
+#[test]
+fn soketto_interop_window_bits() {
+    let mut extension = Deflate::new(Mode::Client);
+    extension.set_max_server_window_bits(10);
+    extension.set_max_client_window_bits(9);
+
+    let config = "client_max_window_bits=9".parse().unwrap();
+
+    soketto_echo(config, extension, &json_messages());
+}
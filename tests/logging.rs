@@ -0,0 +1,36 @@
+//! Redaction of credentials in the logged message payloads.
+
+use servers::{
+    logging::{self, LoggingConfig, PayloadLogging},
+    server::Message,
+};
+
+/// Returns the payload of the text message formatted for logs.
+fn logged(text: &str) -> String {
+    logging::payload(&Message::Text(text.to_string()))
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn credentials_are_redacted() {
+    // credentials are redacted even if payloads are logged in full
+    let config = LoggingConfig {
+        payloads: PayloadLogging::Full,
+        ..Default::default()
+    };
+    logging::init(&config, true).unwrap();
+
+    assert_eq!(logged("/login user password"), "/login <redacted>");
+    // aliases of the commands
+    assert_eq!(logged("/auth user password"), "/auth <redacted>");
+    assert_eq!(logged("/auth token"), "/auth <redacted>");
+    assert_eq!(logged("/useradd user password"), "/useradd <redacted>");
+    assert_eq!(logged("/join room password"), "/join <redacted>");
+    assert_eq!(logged("/roompass room password"), "/roompass <redacted>");
+    assert_eq!(logged("/resume token"), "/resume <redacted>");
+    assert_eq!(logged("Session token: token"), "Session token: <redacted>");
+
+    assert_eq!(logged("/msg 1 hello"), "/msg 1 hello");
+    assert_eq!(logged("hello /auth"), "hello /auth");
+}
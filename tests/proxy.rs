@@ -0,0 +1,272 @@
+use std::{
+    io::{Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+};
+
+use servers::server::{forwarded_for, read_proxy_header};
+
+/// Data sent by the client after the PROXY protocol header.
+const DATA: &[u8] = b"/help";
+
+/// Send the bytes from the proxy and read the header on the server side.
+/// Returns the result and the data left in the stream after the header.
+fn read_header(bytes: &[u8]) -> (anyhow::Result<Option<SocketAddr>>, Vec<u8>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+    let mut proxy = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    proxy.write_all(bytes).unwrap();
+    proxy.shutdown(Shutdown::Write).unwrap();
+
+    let (mut stream, _addr) = listener.accept().unwrap();
+
+    let result = read_proxy_header(&stream);
+
+    let mut rest = Vec::new();
+    if result.is_ok() {
+        stream.read_to_end(&mut rest).unwrap();
+    }
+
+    (result, rest)
+}
+
+/// Build the PROXY protocol v2 header.
+fn v2_header(command: u8, family: u8, payload: &[u8]) -> Vec<u8> {
+    let mut header = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+    header.push(0x20 | command);
+    header.push(family);
+    header.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    header.extend_from_slice(payload);
+    header
+}
+
+#[test]
+fn v1_tcp4() {
+    let (result, rest) = read_header(b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 9999\r\n/help");
+
+    assert_eq!(
+        result.unwrap(),
+        Some(SocketAddr::new(Ipv4Addr::new(192, 0, 2, 1).into(), 56324))
+    );
+    assert_eq!(rest, DATA);
+}
+
+#[test]
+fn v1_tcp6() {
+    let (result, rest) = read_header(b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 9999\r\n/help");
+
+    let ip: Ipv6Addr = "2001:db8::1".parse().unwrap();
+
+    assert_eq!(result.unwrap(), Some(SocketAddr::new(ip.into(), 56324)));
+    assert_eq!(rest, DATA);
+}
+
+#[test]
+fn v1_unknown() {
+    let (result, rest) = read_header(b"PROXY UNKNOWN\r\n/help");
+
+    assert_eq!(result.unwrap(), None);
+    assert_eq!(rest, DATA);
+}
+
+#[test]
+fn v1_invalid() {
+    // family doesn't match the address
+    assert!(
+        read_header(b"PROXY TCP4 2001:db8::1 2001:db8::2 56324 9999\r\n")
+            .0
+            .is_err()
+    );
+    // missing destination port
+    assert!(read_header(b"PROXY TCP4 192.0.2.1 192.0.2.2 56324\r\n")
+        .0
+        .is_err());
+    // invalid port
+    assert!(
+        read_header(b"PROXY TCP4 192.0.2.1 192.0.2.2 65536 9999\r\n")
+            .0
+            .is_err()
+    );
+    // unknown protocol
+    assert!(
+        read_header(b"PROXY UDP4 192.0.2.1 192.0.2.2 56324 9999\r\n")
+            .0
+            .is_err()
+    );
+}
+
+#[test]
+fn v1_truncated() {
+    assert!(read_header(b"PROX").0.is_err());
+    assert!(read_header(b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 99")
+        .0
+        .is_err());
+}
+
+#[test]
+fn v1_too_long() {
+    let mut header = b"PROXY TCP4 ".to_vec();
+    header.extend_from_slice(&[b'1'; 200]);
+    header.extend_from_slice(b"\r\n");
+
+    assert!(read_header(&header).0.is_err());
+}
+
+#[test]
+fn v2_inet() {
+    let mut payload = vec![192, 0, 2, 1, 192, 0, 2, 2];
+    payload.extend_from_slice(&56324u16.to_be_bytes());
+    payload.extend_from_slice(&9999u16.to_be_bytes());
+    // TLVs are ignored
+    payload.extend_from_slice(&[0x04, 0x00, 0x01, 0xFF]);
+
+    let mut header = v2_header(0x1, 0x11, &payload);
+    header.extend_from_slice(DATA);
+
+    let (result, rest) = read_header(&header);
+
+    assert_eq!(
+        result.unwrap(),
+        Some(SocketAddr::new(Ipv4Addr::new(192, 0, 2, 1).into(), 56324))
+    );
+    assert_eq!(rest, DATA);
+}
+
+#[test]
+fn v2_inet6() {
+    let ip: Ipv6Addr = "2001:db8::1".parse().unwrap();
+
+    let mut payload = ip.octets().to_vec();
+    payload.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+    payload.extend_from_slice(&56324u16.to_be_bytes());
+    payload.extend_from_slice(&9999u16.to_be_bytes());
+
+    let mut header = v2_header(0x1, 0x21, &payload);
+    header.extend_from_slice(DATA);
+
+    let (result, rest) = read_header(&header);
+
+    assert_eq!(result.unwrap(), Some(SocketAddr::new(ip.into(), 56324)));
+    assert_eq!(rest, DATA);
+}
+
+#[test]
+fn v2_local() {
+    let mut header = v2_header(0x0, 0x00, &[]);
+    header.extend_from_slice(DATA);
+
+    let (result, rest) = read_header(&header);
+
+    assert_eq!(result.unwrap(), None);
+    assert_eq!(rest, DATA);
+}
+
+#[test]
+fn v2_unknown_family() {
+    // AF_UNIX
+    let mut header = v2_header(0x1, 0x31, &[0; 216]);
+    header.extend_from_slice(DATA);
+
+    let (result, rest) = read_header(&header);
+
+    assert_eq!(result.unwrap(), None);
+    assert_eq!(rest, DATA);
+}
+
+#[test]
+fn v2_invalid() {
+    // unsupported command
+    assert!(read_header(&v2_header(0x2, 0x11, &[0; 12])).0.is_err());
+
+    // unsupported version
+    let mut header = v2_header(0x1, 0x11, &[0; 12]);
+    header[12] = 0x11;
+    assert!(read_header(&header).0.is_err());
+
+    // invalid signature
+    let mut header = v2_header(0x1, 0x11, &[0; 12]);
+    header[6] = b'X';
+    assert!(read_header(&header).0.is_err());
+}
+
+#[test]
+fn v2_truncated() {
+    // signature
+    assert!(read_header(b"\r\n\r\n\0\r\nQU").0.is_err());
+
+    // payload shorter than its length
+    let header = v2_header(0x1, 0x11, &[0; 12]);
+    assert!(read_header(&header[..20]).0.is_err());
+
+    // length too short for the address
+    assert!(read_header(&v2_header(0x1, 0x11, &[0; 4])).0.is_err());
+    assert!(read_header(&v2_header(0x1, 0x21, &[0; 12])).0.is_err());
+}
+
+#[test]
+fn missing_header() {
+    assert!(read_header(b"/help\n").0.is_err());
+    assert!(read_header(b"").0.is_err());
+}
+
+/// Proxies trusted by the tests.
+fn is_trusted(ip: IpAddr) -> bool {
+    ["10.0.0.1", "10.0.0.2"]
+        .iter()
+        .any(|proxy| ip == proxy.parse::<IpAddr>().unwrap())
+}
+
+#[test]
+fn forwarded_for_trusted_proxies() {
+    let peer = "10.0.0.1".parse().unwrap();
+
+    assert_eq!(
+        forwarded_for("192.0.2.1", peer, is_trusted),
+        Some("192.0.2.1".parse().unwrap())
+    );
+    // chain of trusted proxies
+    assert_eq!(
+        forwarded_for("192.0.2.1, 10.0.0.2", peer, is_trusted),
+        Some("192.0.2.1".parse().unwrap())
+    );
+    // addresses with ports
+    assert_eq!(
+        forwarded_for("192.0.2.1:56324", peer, is_trusted),
+        Some("192.0.2.1".parse().unwrap())
+    );
+    assert_eq!(
+        forwarded_for("[2001:db8::1]:56324", peer, is_trusted),
+        Some("2001:db8::1".parse().unwrap())
+    );
+}
+
+#[test]
+fn forwarded_for_untrusted_hops() {
+    let peer = "10.0.0.1".parse().unwrap();
+
+    // addresses left of the first untrusted hop can be spoofed by the client
+    assert_eq!(
+        forwarded_for("10.0.0.2, 198.51.100.1, 192.0.2.1", peer, is_trusted),
+        Some("192.0.2.1".parse().unwrap())
+    );
+    assert_eq!(
+        forwarded_for("198.51.100.1, 192.0.2.1, 10.0.0.2", peer, is_trusted),
+        Some("192.0.2.1".parse().unwrap())
+    );
+
+    // the header of an untrusted peer is ignored
+    let untrusted = "192.0.2.1".parse().unwrap();
+    assert_eq!(forwarded_for("198.51.100.1", untrusted, is_trusted), None);
+}
+
+#[test]
+fn forwarded_for_invalid() {
+    let peer = "10.0.0.1".parse().unwrap();
+
+    assert_eq!(forwarded_for("unknown", peer, is_trusted), None);
+    assert_eq!(forwarded_for("", peer, is_trusted), None);
+    // invalid entries left of the client are ignored
+    assert_eq!(
+        forwarded_for("garbage, 192.0.2.1", peer, is_trusted),
+        Some("192.0.2.1".parse().unwrap())
+    );
+}
@@ -0,0 +1,50 @@
+use std::{
+    io::Write,
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use servers::server::{Client, Message};
+
+/// Connect a TCP client to a new server-side [Client].
+fn connect() -> (Client, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    stream.set_nodelay(true).unwrap();
+
+    let (server_stream, _addr) = listener.accept().unwrap();
+
+    (Client::new_tcp(server_stream, 0), stream)
+}
+
+#[test]
+fn character_split_between_writes() {
+    let (client, mut stream) = connect();
+
+    let reader = client.clone();
+    let reading = thread::spawn(move || reader.read_message().unwrap());
+
+    // `é` is encoded as 0xC3 0xA9
+    stream.write_all(b"/nick caf\xC3").unwrap();
+    thread::sleep(Duration::from_millis(100));
+    stream.write_all(b"\xA9\n").unwrap();
+
+    assert_eq!(
+        reading.join().unwrap(),
+        Message::Text("/nick café".to_string())
+    );
+}
+
+#[test]
+fn invalid_utf8_is_binary() {
+    let (client, mut stream) = connect();
+
+    stream.write_all(b"\xFF\x00\xC3").unwrap();
+
+    assert_eq!(
+        client.read_message().unwrap(),
+        Message::Binary(b"\xFF\x00\xC3".to_vec())
+    );
+}
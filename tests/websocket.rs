@@ -0,0 +1,168 @@
+use std::{
+    io::Write,
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use servers::server::{Client, Message as ClientMessage};
+use tungstenite::{protocol::frame::coding::CloseCode, Message};
+
+/// Connect a WebSocket client to a new server-side [Client].
+fn connect() -> (Client, tungstenite::WebSocket<TcpStream>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        let (stream, _addr) = listener.accept().unwrap();
+        Client::new_websocket(stream, 0).unwrap()
+    });
+
+    let stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let (websocket, _response) = tungstenite::client(format!("ws://{addr}/"), stream).unwrap();
+
+    (server.join().unwrap(), websocket)
+}
+
+#[test]
+fn send_while_reading() {
+    let (client, mut websocket) = connect();
+
+    // block the server in reading, like the message loop of an idle client
+    let reader = client.clone();
+    let reading = thread::spawn(move || reader.read().unwrap());
+
+    thread::sleep(Duration::from_millis(100));
+
+    // e.g. a broadcast from another client
+    client.send("hello").unwrap();
+
+    let msg = websocket.read_message().unwrap();
+    assert_eq!(msg.into_data(), b"hello");
+
+    // the read is still pending and gets the next message
+    websocket
+        .write_message(Message::Text("/id".to_string()))
+        .unwrap();
+    assert_eq!(reading.join().unwrap(), "/id");
+}
+
+#[test]
+fn ping_and_close() {
+    let (client, mut websocket) = connect();
+
+    let reader = client.clone();
+    let reading = thread::spawn(move || reader.read());
+
+    websocket
+        .write_message(Message::Ping(b"ping".to_vec()))
+        .unwrap();
+    assert_eq!(
+        websocket.read_message().unwrap(),
+        Message::Pong(b"ping".to_vec())
+    );
+
+    websocket.close(None).unwrap();
+    assert!(matches!(
+        websocket.read_message().unwrap(),
+        Message::Close(_)
+    ));
+
+    assert_eq!(
+        reading.join().unwrap().unwrap_err().to_string(),
+        "disconnected"
+    );
+}
+
+#[test]
+fn close_with_status_code_not_allowed_in_reply() {
+    let (client, mut websocket) = connect();
+
+    let reader = client.clone();
+    let reading = thread::spawn(move || reader.read());
+
+    // masked close frame with the code 1005 (no status received), which the
+    // server must not send back
+    websocket
+        .get_mut()
+        .write_all(&[0x88, 0x82, 0, 0, 0, 0, 0x03, 0xed])
+        .unwrap();
+
+    match websocket.read_message().unwrap() {
+        Message::Close(Some(frame)) => assert_eq!(frame.code, CloseCode::Normal),
+        msg => panic!("expected close frame, got {msg:?}"),
+    }
+
+    assert_eq!(
+        reading.join().unwrap().unwrap_err().to_string(),
+        "disconnected"
+    );
+}
+
+/// Send the raw frame and check the server closes the connection with the
+/// protocol error status code.
+fn assert_protocol_error(frame: &[u8]) {
+    let (client, mut websocket) = connect();
+
+    let reader = client.clone();
+    let reading = thread::spawn(move || reader.read());
+
+    websocket.get_mut().write_all(frame).unwrap();
+
+    match websocket.read_message().unwrap() {
+        Message::Close(Some(frame)) => assert_eq!(frame.code, CloseCode::Protocol),
+        msg => panic!("expected close frame, got {msg:?}"),
+    }
+
+    assert!(reading.join().unwrap().is_err());
+}
+
+#[test]
+fn fragmented_control_frame() {
+    // masked ping frame without the FIN bit
+    assert_protocol_error(&[0x09, 0x84, 0, 0, 0, 0, b'p', b'i', b'n', b'g']);
+}
+
+#[test]
+fn control_frame_too_long() {
+    // masked ping frame with 126 bytes of payload
+    let mut frame = vec![0x89, 0xFE, 0, 126, 0, 0, 0, 0];
+    frame.extend_from_slice(&[b'a'; 126]);
+
+    assert_protocol_error(&frame);
+}
+
+#[test]
+fn close_with_one_byte_payload() {
+    // masked close frame with a truncated status code
+    assert_protocol_error(&[0x88, 0x81, 0, 0, 0, 0, 0x03]);
+}
+
+#[test]
+fn binary_messages() {
+    let (client, mut websocket) = connect();
+
+    websocket
+        .write_message(Message::Binary(vec![0, 159, 146, 150]))
+        .unwrap();
+    assert_eq!(
+        client.read_message().unwrap(),
+        ClientMessage::Binary(vec![0, 159, 146, 150])
+    );
+
+    client.send_message(vec![1, 2, 3]).unwrap();
+    client.send("text").unwrap();
+
+    assert_eq!(
+        websocket.read_message().unwrap(),
+        Message::Binary(vec![1, 2, 3])
+    );
+    assert_eq!(
+        websocket.read_message().unwrap(),
+        Message::Text("text".to_string())
+    );
+}